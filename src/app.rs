@@ -1,30 +1,83 @@
 use std::{
-    collections::{HashMap, HashSet},
+    cmp::Reverse,
+    collections::HashSet,
     fs::{self, File},
-    io::{BufReader, BufWriter, Read, Write},
+    hash::{DefaultHasher, Hash, Hasher},
+    io::{BufReader, BufWriter, Write},
     marker::PhantomData,
-    path::Path,
+    path::{Path, PathBuf},
+    sync::{
+        mpsc::{self, Receiver, TryRecvError},
+        Arc,
+    },
+    thread,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
 };
 
 use anyhow::{anyhow, bail, Context};
+use backup::BACKUP_DIR;
 use egui::{
-    emath, vec2, Button, Color32, DragAndDrop, FontFamily, FontId, Grid, Id, InnerResponse,
-    LayerId, Order, Rangef, Rect, Sense, TextStyle, Ui, UiBuilder, Window,
+    emath, pos2, vec2, Align2, Button, Color32, DragAndDrop, DroppedFile, FontFamily, FontId,
+    Frame, Grid, Id, InnerResponse, Key, KeyboardShortcut, LayerId, Modifiers, Order, ProgressBar,
+    Rangef, Rect, ScrollArea, Sense, TextEdit, TextStyle, Ui, UiBuilder, ViewportCommand, Window,
 };
-use modpack::{modsettings::ModSettings, ModPack};
-
-use xmltree::{Element, XMLNode};
+use loader::{KeptEntries, LoadMessage, LoadPaths, LoadStats, MeasuredSize};
+use modpack::{
+    modsettings::ModSettings, ApplyResult, ModApplyMode, ModPack, PackCompression,
+    SettingsApplyMode,
+};
+use session_order::SESSION_ORDER_FILE;
+pub use state::StartupLayout;
+use state::{PersistentState, SwapSlot, STATE_FILE};
+use user_data::{UserData, USER_DATA_FILE};
 
+use crate::icons::YELLOW;
 use crate::r#mod::{
-    conditional::Condition, GitHost, GitMod, Mod, ModKind, ModSource, NormalMod, SteamMod,
+    conditional::{Condition, ParsedSearch, SearchContext},
+    Mod, ModKind, NormalMod,
 };
 
+mod backup;
+mod conflicts;
+mod load_order;
+mod loader;
 mod modpack;
+mod name_order;
+mod session_order;
+mod state;
+mod user_data;
 
 pub const SCALE: f32 = 1.6;
+/// the window size on startup and after resetting the layout
+const WINDOW_SIZE: [f32; 2] = [320.0, 240.0];
+const SAVE_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::S);
+const SETTINGS_PANEL_SHORTCUT: KeyboardShortcut = KeyboardShortcut::new(Modifiers::COMMAND, Key::B);
+const SAVE_DISABLED_REASON: &str =
+    "Cannot save when there was an error starting the mod manager, fix the errors then save.";
+const ENABLED_TINT: Color32 = Color32::from_rgba_premultiplied(40, 90, 40, 120);
+const ENABLED_TINT_WIDTH: f32 = 3.0;
+/// background of mods in the modpack being hovered
+const PACK_HIGHLIGHT: Color32 = Color32::from_rgba_premultiplied(70, 60, 20, 70);
+/// how long typing has to pause for before the mod list is refiltered
+const SEARCH_DEBOUNCE: Duration = Duration::from_millis(150);
+/// how long the load order has to stay the same before auto-save writes it, long as it writes game files
+const AUTO_SAVE_DEBOUNCE: Duration = Duration::from_secs(3);
+/// how long the saved indicator stays after an auto-save
+const SAVED_INDICATOR_TIME: Duration = Duration::from_secs(2);
 
 #[derive(Copy, Clone, Debug)]
 struct DNDPayload(usize);
+#[derive(Copy, Clone, Debug)]
+struct PackDNDPayload(usize);
+
+/// like [`DragAndDrop::take_payload`] but leaves payloads of other types alone, so the mod list and modpack list don't steal each other's drags
+fn take_payload_of_type<T: Send + Sync + 'static>(ctx: &egui::Context) -> Option<Arc<T>> {
+    if DragAndDrop::has_payload_of_type::<T>(ctx) {
+        DragAndDrop::take_payload::<T>(ctx)
+    } else {
+        None
+    }
+}
 #[derive(Clone, Debug)]
 struct Popup<'a> {
     content: String,
@@ -32,24 +85,149 @@ struct Popup<'a> {
     id: usize,
 }
 
+/// how far each popup is moved from the one before it so stacked popups don't hide each other
+const POPUP_STACK_OFFSET: f32 = 24.0;
+
 impl<'a> Popup<'a> {
-    /// returns if the popup is still open
-    fn show(&self, ctx: &egui::Context) -> bool {
+    /// returns if the popup is still open, `index` is its place in the stack and only affects where it first appears
+    fn show(&self, ctx: &egui::Context, index: usize) -> bool {
         let mut open = true;
+        // only applies on the first frame, after that egui remembers where the window is by its id
+        let offset = POPUP_STACK_OFFSET * (index % 10) as f32;
         Window::new(self.title)
-            .id(Id::new(self.id))
+            .id(Id::new(("Popup", self.id)))
+            .default_pos(pos2(40.0 + offset, 40.0 + offset))
+            .default_width(400.0)
+            .max_height(ctx.screen_rect().height() * 0.6)
             .open(&mut open)
             .show(ctx, |ui| {
-                ui.label(&self.content);
+                if ui.button("Copy").clicked() {
+                    ui.ctx().copy_text(self.content.clone());
+                }
+                ScrollArea::vertical().show(ui, |ui| {
+                    // read only, but selectable so parts of it can be copied
+                    ui.add(
+                        TextEdit::multiline(&mut self.content.as_str())
+                            .desired_width(f32::INFINITY)
+                            .code_editor(),
+                    );
+                });
             });
         open
     }
 }
 
+/// one line per context in the chain, outermost first, which reads better than anyhow's debug output when copied
+fn format_error(error: &anyhow::Error) -> String {
+    let mut lines = error.chain().map(|e| e.to_string());
+    let mut out = lines.next().unwrap_or_default();
+    for line in lines {
+        out += "\n  caused by: ";
+        out += &line;
+    }
+    out
+}
+
+/// How bad a banner is, only changes how it is drawn
+#[derive(Copy, Clone, Debug, PartialEq)]
+enum Severity {
+    /// not a problem, just feedback for something the user asked for
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn color(self) -> Color32 {
+        match self {
+            Severity::Info => Color32::from_rgb(30, 50, 80),
+            Severity::Warning => Color32::from_rgb(90, 70, 10),
+            Severity::Error => Color32::from_rgb(100, 25, 25),
+        }
+    }
+}
+
+/// The inspect pack window, the pack in it is only shown and never applied or saved
+#[derive(Default)]
+struct InspectPack {
+    path: String,
+    pack: Option<ModPack>,
+    /// a copy of the pack's settings grouped for showing, edits to it go nowhere
+    settings: ModSettings,
+}
+
+#[derive(Default)]
 struct ModListConfig {
     search: String,
+    /// the search that filtering uses, lags behind `search` while typing
+    applied_search: String,
+    search_edited: Option<Instant>,
+    parsed_search: ParsedSearch,
     mods: Vec<Mod>,
     mod_settings: ModSettings,
+    /// ids of the mods picked with ctrl click, for the batch toolbar
+    selected: HashSet<String>,
+    /// config entries of a source that was turned off, saved back untouched
+    /// each is after that many of `mods`, so they stay roughly where they were
+    unloaded: KeptEntries,
+    /// narrows down the settings include tree
+    settings_filter: String,
+}
+
+impl ModListConfig {
+    /// ids of enabled normal mods in load order
+    fn enabled_mod_ids(&self) -> Vec<String> {
+        self.mods
+            .iter()
+            .filter(|e| {
+                if let ModKind::Normal(nmod) = e.kind {
+                    nmod.enabled
+                } else {
+                    false
+                }
+            })
+            .map(|e| e.id.clone())
+            .collect()
+    }
+
+    /// only normal mods can be enabled, anything else selected is left alone
+    fn set_selected_enabled(&mut self, enabled: bool) {
+        for nmod in self.mods.iter_mut() {
+            if let ModKind::Normal(normal_mod) = &mut nmod.kind {
+                if self.selected.contains(&nmod.id) {
+                    normal_mod.enabled = enabled;
+                }
+            }
+        }
+    }
+
+    /// only normal mods the search shows are changed, so a search narrows down what gets toggled
+    /// mods that failed to load are left alone, the same as their checkbox
+    fn set_shown_enabled(&mut self, search: &ParsedSearch, context: &SearchContext, enabled: bool) {
+        for nmod in self.mods.iter_mut() {
+            if nmod.load_error.is_some() || !search.matches(nmod, context) {
+                continue;
+            }
+            if let ModKind::Normal(normal_mod) = &mut nmod.kind {
+                normal_mod.enabled = enabled;
+            }
+        }
+    }
+
+    /// moves the selected mods to the start or end of the load order, keeping their order among themselves
+    fn move_selected(&mut self, to_top: bool) {
+        let (mut moved, rest): (Vec<_>, Vec<_>) = self
+            .mods
+            .drain(..)
+            .partition(|e| self.selected.contains(&e.id));
+        if to_top {
+            moved.extend(rest);
+            self.mods = moved;
+        } else {
+            self.mods = rest;
+            self.mods.extend(moved);
+        }
+    }
 }
 
 struct ModPackConfig {
@@ -57,6 +235,8 @@ struct ModPackConfig {
     modpacks: Vec<ModPack>,
     row_rect: Option<Rect>,
     installed_mods: HashSet<String>,
+    /// mods in the hovered pack, set by the modpack panel every frame before the mod list is drawn
+    highlighted: HashSet<String>,
 }
 
 pub struct App<'a, 'b, 'c> {
@@ -68,9 +248,55 @@ pub struct App<'a, 'b, 'c> {
     workshop_dir: Option<&'a Path>,
     mod_settings_file: &'a Path,
     popups: Vec<Popup<'b>>,
+    /// non-blocking problems shown above the mod list until dismissed, popups are for ones that stop things working
+    errors: Vec<(Severity, String)>,
     global_id: usize,
     row_rect: Option<Rect>,
     init_errored: bool,
+    /// the settings file couldn't be read, so saving leaves it alone rather than writing empty settings over it
+    settings_failed: bool,
+    /// applied on the first frame the panels are shown, as the settings focus needs the window size
+    pending_layout: Option<StartupLayout>,
+    /// `None` until loading has succeeded
+    load_stats: Option<LoadStats>,
+    /// [`App::mods_fingerprint`] when the mod config was last saved or loaded
+    saved_fingerprint: u64,
+    /// the fingerprint auto-save is waiting on and when it changed to it
+    pending_save: Option<(u64, Instant)>,
+    last_auto_save: Option<Instant>,
+    /// mod sizes still being measured by the loading thread, `None` once they are all in
+    sizes: Option<Receiver<MeasuredSize>>,
+    state: PersistentState,
+    user_data: UserData,
+    /// the user data file couldn't be read, so changes stay in memory rather than being written over it
+    user_data_failed: bool,
+    /// contents of the add tag box in the mod context menu
+    new_tag: String,
+    /// contents of the import load order window, which is open while this is `Some`
+    import_text: Option<String>,
+    /// the inspect pack window, open while this is `Some`
+    inspect: Option<InspectPack>,
+    /// the incomplete workshop mods window, open while this is `Some`
+    incomplete_dirs: Option<Vec<PathBuf>>,
+    /// the incomplete workshop mods window is asking whether to delete them
+    confirm_delete_dirs: bool,
+    /// when the manager was started before this run, for marking updated workshop mods
+    last_run: Option<SystemTime>,
+    /// the restore backup window, open while this is `Some`
+    backups: Option<Vec<backup::Backup>>,
+    /// the about window, which also has the reset layout button
+    about_open: bool,
+    /// set by the reset layout button, done at the start of the next frame before any panel is shown
+    reset_layout: bool,
+    /// only the first load picks up the order from last session, reloading goes back to the game's config
+    restore_session_order: bool,
+    /// the tag box of the batch toolbar
+    batch_tag: String,
+    /// `Some` until the background load has finished, the panels aren't shown until then
+    loading: Option<Loading>,
+    scroll: ScrollOffsets,
+    /// set after the lists are rebuilt so they jump back to where the user was
+    restore_scroll: bool,
 
     #[allow(dead_code)]
     profiler: ProfilerInfo<'c>,
@@ -80,18 +306,108 @@ pub struct App<'a, 'b, 'c> {
 pub struct ProfilerInfo<'a> {
     pub frame_counter: u64,
     pub profiler: pprof::ProfilerGuard<'a>,
+    /// frames drawn since `window_start`, logged every [`FRAME_LOG_INTERVAL`] to check the manager idles
+    pub window_frames: u64,
+    pub window_start: Instant,
 }
 
+/// how often the profiler logs the frame count
+#[cfg(feature = "profiler")]
+const FRAME_LOG_INTERVAL: Duration = Duration::from_secs(10);
+
 #[cfg(not(feature = "profiler"))]
 pub struct ProfilerInfo<'a> {
     pub profiler: PhantomData<&'a ()>,
 }
 
+/// Vertical scroll offsets of the lists, egui forgets them when the lists are rebuilt
+#[derive(Copy, Clone, Debug, Default)]
+struct ScrollOffsets {
+    mods: f32,
+    modpacks: f32,
+    settings: f32,
+}
+
+fn restorable_scroll_area(id: &str, offset: f32, restore: bool) -> ScrollArea {
+    let area = ScrollArea::vertical().id_salt(id).auto_shrink(false);
+    if restore {
+        area.vertical_scroll_offset(offset)
+    } else {
+        area
+    }
+}
+
+struct Loading {
+    receiver: Receiver<LoadMessage>,
+    loaded: usize,
+    total: usize,
+}
+
 #[derive(Clone, Debug)]
 pub struct ModConfigItem {
     pub id: String,
     /// This is from the config, so the bool might just be nonsense if it's not a normal mod
     pub enabled: bool,
+    pub settings_fold_open: bool,
+    /// "0" for mods that aren't from the workshop
+    pub workshop_item_id: String,
+}
+
+impl ModConfigItem {
+    fn xml_line(&self) -> String {
+        format!(
+            "\t<Mod enabled=\"{}\" name=\"{}\" settings_fold_open=\"{}\" workshop_item_id=\"{}\" />\n",
+            self.enabled as usize,
+            escape_attribute(&self.id),
+            self.settings_fold_open as usize,
+            escape_attribute(&self.workshop_item_id)
+        )
+    }
+}
+
+/// for a double quoted xml attribute, whitespace is kept as references as parsers turn raw newlines and tabs into spaces
+fn escape_attribute(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped += "&amp;",
+            '<' => escaped += "&lt;",
+            '>' => escaped += "&gt;",
+            '"' => escaped += "&quot;",
+            '\t' | '\n' | '\r' => escaped += &format!("&#{};", c as u32),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// the contents of the game's mod config, unloaded entries go back where they were relative to the loaded mods
+fn config_xml(mods: &[Mod], unloaded: &KeptEntries) -> String {
+    let mut unloaded = unloaded.iter().peekable();
+    let mut buf = "<Mods>\n".to_string();
+    for (i, nmod) in mods.iter().enumerate() {
+        while let Some((_, item)) = unloaded.next_if(|e| e.0 <= i) {
+            buf += &item.xml_line();
+        }
+        buf += &ModConfigItem::from(nmod).xml_line();
+    }
+    for (_, item) in unloaded {
+        buf += &item.xml_line();
+    }
+    buf += "</Mods>";
+    buf
+}
+
+/// only normal mods can be enabled, whatever the config said for the others is dropped here
+impl From<&Mod> for ModConfigItem {
+    fn from(nmod: &Mod) -> ModConfigItem {
+        ModConfigItem {
+            id: nmod.id.clone(),
+            enabled: matches!(nmod.kind, ModKind::Normal(NormalMod { enabled: true })),
+            settings_fold_open: nmod.settings_fold_open,
+            workshop_item_id: nmod.source.workshop_item_id().to_owned(),
+        }
+    }
 }
 
 impl<'d, 'e, 'f> App<'d, 'e, 'f> {
@@ -101,152 +417,1242 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
                 self.pack_config.row_rect = Some(
                     pack.render(
                         ui,
-                        &mut self.list_config,
-                        &mut "".to_owned(),
                         &HashSet::new(),
+                        &HashSet::new(),
+                        ModApplyMode::Replace,
                         false,
                         None,
                     )
                     .response
                     .rect,
                 );
-                ui.ctx().request_repaint();
+                // the measuring row shouldn't be shown, this redoes the pass instead of painting it
+                ui.ctx().request_discard("Measuring modpack rows");
             }
         }
 
-        ui.horizontal(|ui| {
-            ui.label("Search");
-            ui.text_edit_singleline(&mut self.pack_config.name);
-        });
-        if ui.button("Export as modpack").clicked() {
-            let pack = ModPack::new(
+        let search = ui
+            .horizontal(|ui| {
+                ui.label("Search");
+                ui.text_edit_singleline(&mut self.pack_config.name)
+                    .on_hover_text("Press enter to apply the pack if only one matches")
+            })
+            .inner;
+        if search.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)) {
+            self.apply_searched_pack();
+        }
+        let (export, snapshot, compression_changed) = ui
+            .horizontal(|ui| {
+                let export = ui.button("Export as modpack").clicked();
+                let snapshot = ui
+                    .button("Snapshot current config")
+                    .on_hover_text(
+                        "Save the enabled mods and all settings as a timestamped modpack",
+                    )
+                    .clicked();
+                if ui
+                    .button("Inspect pack")
+                    .on_hover_text("Look inside a modpack file without importing or applying it")
+                    .clicked()
+                {
+                    self.inspect.get_or_insert_with(Default::default);
+                }
+                let mut gzip = self.state.pack_compression == PackCompression::Gzip;
+                let compression_changed = ui
+                    .checkbox(&mut gzip, "Gzip")
+                    .on_hover_text("Compress packs when saving them, for sharing large packs")
+                    .changed();
+                self.state.pack_compression = if gzip {
+                    PackCompression::Gzip
+                } else {
+                    PackCompression::None
+                };
+                let settings_changed = ui
+                    .checkbox(&mut self.state.export_without_settings, "No settings")
+                    .on_hover_text(
+                        "Export packs that only set the mod list and never change settings when applied",
+                    )
+                    .changed();
+                (export, snapshot, compression_changed | settings_changed)
+            })
+            .inner;
+        if compression_changed {
+            self.save_state();
+        }
+        if export {
+            let mut pack = ModPack::new(
                 self.pack_config.name.clone(),
                 self.pack_config.name.clone(),
-                &self
-                    .list_config
-                    .mods
-                    .iter()
-                    .filter(|e| {
-                        if let ModKind::Normal(nmod) = e.kind {
-                            nmod.enabled
-                        } else {
-                            false
-                        }
-                    })
-                    .map(|e| e.id.clone())
-                    .collect::<Vec<_>>(),
+                &self.list_config.enabled_mod_ids(),
                 &self.list_config.mod_settings,
             );
-            let path = Path::new("./modpacks/").join(&self.pack_config.name);
-            pack.save(BufWriter::new(File::create(path).context(format!(
-                "Creating modpack {}",
-                &self.pack_config.name
-            ))?))
-            .context(format!("Saving modpack {}", &self.pack_config.name))?;
-            if let Some(found) = self
-                .pack_config
-                .modpacks
-                .iter_mut()
-                .find(|e| e.file_name() == pack.file_name())
-            {
-                *found = pack;
-            } else {
-                self.pack_config.modpacks.push(pack);
-            }
-        }
-        egui::ScrollArea::vertical()
-            .auto_shrink(false)
-            .show(ui, |ui| {
-                let mut error = None;
-                let searching_name = self.pack_config.name.clone();
-                Grid::new("Modpack Grid").striped(false).show(ui, |ui| {
-                    for (i, modpack) in self
-                        .pack_config
-                        .modpacks
-                        .iter()
-                        .filter(|e| e.name().contains(&searching_name))
-                        .enumerate()
+            if self.state.export_without_settings {
+                pack = pack.without_settings();
+            }
+            self.save_modpack(pack)?;
+        }
+        if snapshot {
+            let timestamp = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .context("Getting snapshot timestamp")?
+                .as_secs();
+            let name = format!("Snapshot {timestamp}");
+            // snapshots ignore the include tree, they are a backup of everything
+            let mut settings = self.list_config.mod_settings.clone();
+            settings.grouped.include_all(true);
+            let pack = ModPack::new(
+                name.clone(),
+                name,
+                &self.list_config.enabled_mod_ids(),
+                &settings,
+            );
+            self.save_modpack(pack)?;
+        }
+        let next_slot = self
+            .state
+            .quick_swap
+            .active
+            .map(SwapSlot::other)
+            .unwrap_or(SwapSlot::A);
+        let (swap, save_after_swap_changed) = ui
+            .horizontal(|ui| {
+                let quick_swap = &mut self.state.quick_swap;
+                let swap = ui
+                    .add_enabled(
+                        quick_swap.slot(next_slot).is_some(),
+                        Button::new(format!("Swap to {next_slot:?}")),
+                    )
+                    .on_hover_text(match quick_swap.slot(next_slot) {
+                        Some(file_name) => format!("Apply {file_name}"),
+                        None => format!("Bind a pack to slot {next_slot:?} to swap to it"),
+                    })
+                    .on_disabled_hover_text(format!(
+                        "Bind a pack to slot {next_slot:?} to swap to it"
+                    ))
+                    .clicked();
+                let changed = ui
+                    .checkbox(&mut quick_swap.save_after_swap, "Save after swap")
+                    .on_hover_text("Save the mod config for the game after every swap")
+                    .changed();
+                (swap, changed)
+            })
+            .inner;
+        let mod_mode_changed = ui
+            .horizontal(|ui| {
+                ui.label("Mods on apply");
+                let mut changed = false;
+                for mode in ModApplyMode::ALL {
+                    changed |= ui
+                        .selectable_value(&mut self.state.mod_apply_mode, mode, mode.label())
+                        .on_hover_text(mode.description())
+                        .changed();
+                }
+                changed
+            })
+            .inner;
+        let apply_mode_changed = ui
+            .horizontal(|ui| {
+                ui.label("Settings on apply");
+                let mut changed = false;
+                for mode in SettingsApplyMode::ALL {
+                    changed |= ui
+                        .selectable_value(&mut self.state.settings_apply_mode, mode, mode.label())
+                        .on_hover_text(mode.description())
+                        .changed();
+                }
+                changed
+            })
+            .inner;
+        if save_after_swap_changed || mod_mode_changed || apply_mode_changed {
+            self.save_state();
+        }
+        if swap {
+            self.swap_packs(next_slot)?;
+        }
+        let output =
+            restorable_scroll_area("Modpack Scroll", self.scroll.modpacks, self.restore_scroll)
+                .show(ui, |ui| {
+                    // taking the payload clears it, the handle sets it again while it's still dragged
+                    let payload = take_payload_of_type::<PackDNDPayload>(ui.ctx());
+                    let mut drop_target = None;
+                    let mut applied = None;
+                    let mut slots_changed = false;
+                    let mut hovered_pack = None;
+                    let searching_name = self.pack_config.name.trim().to_lowercase();
+                    let enabled = self
+                        .list_config
+                        .enabled_mod_ids()
+                        .into_iter()
+                        .collect::<HashSet<_>>();
+                    if !self.pack_config.modpacks.is_empty()
+                        && !self
+                            .pack_config
+                            .modpacks
+                            .iter()
+                            .any(|e| e.matches_search(&searching_name))
                     {
-                        // if we just saved the first pack then row rect can be in a bad state here, just draw a frame later
-                        if self.pack_config.row_rect == None {
-                            return;
-                        }
-                        if let Some(err) = modpack
-                            .render(
+                        ui.vertical_centered(|ui| ui.label("No modpacks match this name"));
+                    }
+                    Grid::new("Modpack Grid").striped(false).show(ui, |ui| {
+                        for (i, modpack) in self
+                            .pack_config
+                            .modpacks
+                            .iter()
+                            .filter(|e| e.matches_search(&searching_name))
+                            .enumerate()
+                        {
+                            // if we just saved the first pack then row rect can be in a bad state here, just draw a frame later
+                            if self.pack_config.row_rect == None {
+                                return;
+                            }
+                            let handle = ui.horizontal(|ui| {
+                                ui.dnd_drag_source(
+                                    Id::new(("Modpack DND", i)),
+                                    PackDNDPayload(i),
+                                    |ui| ui.label("☰"),
+                                )
+                                .response
+                                .on_hover_cursor(egui::CursorIcon::Grab)
+                                .on_hover_text("Drag to reorder");
+                                for slot in [SwapSlot::A, SwapSlot::B] {
+                                    let bound =
+                                        self.state.quick_swap.slot(slot).map(|e| e.as_str())
+                                            == Some(modpack.file_name());
+                                    if ui
+                                        .selectable_label(bound, format!("{slot:?}"))
+                                        .on_hover_text(format!("Bind to quick swap slot {slot:?}"))
+                                        .clicked()
+                                    {
+                                        *self.state.quick_swap.slot_mut(slot) = if bound {
+                                            None
+                                        } else {
+                                            Some(modpack.file_name().to_owned())
+                                        };
+                                        slots_changed = true;
+                                    }
+                                }
+                            });
+                            let rendered = modpack.render(
                                 ui,
-                                &mut self.list_config,
-                                &mut self.pack_config.name,
                                 &self.pack_config.installed_mods,
+                                &enabled,
+                                self.state.mod_apply_mode,
                                 i % 2 == 0,
                                 self.pack_config.row_rect,
-                            )
-                            .inner
+                            );
+                            let row = handle.response.rect.union(rendered.response.rect);
+                            if ui.rect_contains_pointer(row) {
+                                hovered_pack = Some(i);
+                            }
+                            if payload.is_some() {
+                                if let Some(pointer) = ui.ctx().pointer_interact_pos() {
+                                    if row.y_range().contains(pointer.y) {
+                                        let stroke = egui::Stroke::new(1.0, Color32::WHITE);
+                                        if pointer.y > row.center().y {
+                                            ui.painter().hline(row.x_range(), row.bottom(), stroke);
+                                            drop_target = Some(i + 1);
+                                        } else {
+                                            ui.painter().hline(row.x_range(), row.top(), stroke);
+                                            drop_target = Some(i);
+                                        }
+                                    }
+                                }
+                            }
+                            let response = rendered.inner;
+                            if response.apply_clicked {
+                                self.pack_config.name = modpack.name().to_owned();
+                                let result = modpack.apply(
+                                    &mut self.list_config,
+                                    &self.pack_config.installed_mods,
+                                    self.state.mod_apply_mode,
+                                    self.state.settings_apply_mode,
+                                );
+                                applied = Some((modpack.name().to_owned(), result));
+                            }
+                            ui.end_row();
+                        }
+                    });
+                    self.pack_config.highlighted = hovered_pack
+                        .and_then(|i| {
+                            self.pack_config
+                                .modpacks
+                                .iter()
+                                .filter(|e| e.matches_search(&searching_name))
+                                .nth(i)
+                        })
+                        .map(|e| e.mods().iter().cloned().collect())
+                        .unwrap_or_default();
+                    if slots_changed {
+                        self.save_state();
+                    }
+                    if let Some((name, result)) = applied {
+                        self.restore_scroll = true;
+                        self.report_apply(&name, &result);
+                    }
+                    match (payload, drop_target) {
+                        (Some(payload), Some(to_idx))
+                            if !ui.ctx().input(|i| i.pointer.any_down()) =>
                         {
-                            error = Some(err);
+                            self.move_modpack(payload.0, to_idx, &searching_name)
                         }
-                        ui.end_row();
+                        _ => Ok(()),
                     }
                 });
-                if let Some(err) = error {
-                    self.create_error(anyhow!(err));
+        self.scroll.modpacks = output.state.offset.y;
+        output.inner
+    }
+
+    /// `from_idx` and `to_idx` are positions in the list filtered by `searching_name`, the new order is saved
+    fn move_modpack(
+        &mut self,
+        from_idx: usize,
+        to_idx: usize,
+        searching_name: &str,
+    ) -> anyhow::Result<()> {
+        let modpacks = &mut self.pack_config.modpacks;
+        let filtered = modpacks
+            .iter()
+            .enumerate()
+            .filter(|(_, e)| e.matches_search(searching_name))
+            .map(|(i, _)| i)
+            .collect::<Vec<_>>();
+        let from = *filtered
+            .get(from_idx)
+            .context("Dragged modpack should exist")?;
+        // dragging past the last filtered pack puts it at the very end
+        let mut to = filtered.get(to_idx).copied().unwrap_or(modpacks.len());
+        if from == to {
+            return Ok(());
+        }
+        let pack = modpacks.remove(from);
+        if to > from {
+            to -= 1;
+        }
+        modpacks.insert(to, pack);
+        modpack::save_order(
+            &Path::new("./modpacks/").join(modpack::ORDER_FILE),
+            modpacks,
+        )
+    }
+
+    /// enter in the search box, only applies when the search narrows it down to one pack
+    fn apply_searched_pack(&mut self) {
+        let searching_name = self.pack_config.name.trim().to_lowercase();
+        let mut matching = self
+            .pack_config
+            .modpacks
+            .iter()
+            .filter(|e| e.matches_search(&searching_name));
+        let pack = match (matching.next(), matching.count()) {
+            (Some(pack), 0) => pack,
+            (None, _) => {
+                self.create_banner(Severity::Info, "No modpacks match this name".to_owned());
+                return;
+            }
+            (Some(_), rest) => {
+                self.create_banner(
+                    Severity::Info,
+                    format!("{} modpacks match, type more to pick one", rest + 1),
+                );
+                return;
+            }
+        };
+        self.pack_config.name = pack.name().to_owned();
+        let result = pack.apply(
+            &mut self.list_config,
+            &self.pack_config.installed_mods,
+            self.state.mod_apply_mode,
+            self.state.settings_apply_mode,
+        );
+        let name = self.pack_config.name.clone();
+        self.restore_scroll = true;
+        self.report_apply(&name, &result);
+    }
+
+    /// applies the pack bound to `slot` and marks it as the active one
+    fn swap_packs(&mut self, slot: SwapSlot) -> anyhow::Result<()> {
+        let file_name = self
+            .state
+            .quick_swap
+            .slot(slot)
+            .context(format!("Quick swap slot {slot:?} is empty"))?
+            .clone();
+        let pack = self
+            .pack_config
+            .modpacks
+            .iter()
+            .find(|e| e.file_name() == file_name)
+            .context(format!(
+                "Finding modpack {file_name} for quick swap slot {slot:?}"
+            ))?;
+        // swapping has to turn the other pack's mods off, so it always replaces
+        let result = pack.apply(
+            &mut self.list_config,
+            &self.pack_config.installed_mods,
+            ModApplyMode::Replace,
+            self.state.settings_apply_mode,
+        );
+        let name = pack.name().to_owned();
+        self.restore_scroll = true;
+
+        self.state.quick_swap.active = Some(slot);
+        self.save_state();
+        self.report_apply(&name, &result);
+        if self.state.quick_swap.save_after_swap {
+            if self.init_errored {
+                bail!(
+                    "Cannot save after swapping when there was an error starting the mod manager"
+                );
+            }
+            self.save_mods().context("Saving mod config after swap")?;
+        }
+        Ok(())
+    }
+
+    /// what applying the pack `name` changed, with warnings for anything that couldn't be applied
+    fn report_apply(&mut self, name: &str, result: &ApplyResult) {
+        self.create_banner(
+            Severity::Info,
+            format!("Applied {name}: {}", result.summary()),
+        );
+        if !result.missing.is_empty() {
+            self.create_banner(
+                Severity::Warning,
+                format!("{name}: Missing mods:\n{}", result.missing.join("\n")),
+            );
+        }
+        let skipped = result.settings_skipped;
+        if skipped != 0 {
+            self.create_banner(
+                Severity::Warning,
+                format!("Skipped {skipped} settings for mods that aren't installed"),
+            );
+        }
+    }
+
+    /// copies the user data of the mod with `id` to [`UserData`] and saves it
+    fn save_user_data(&mut self, id: &str) {
+        if let Some(nmod) = self.list_config.mods.iter().find(|e| e.id == id) {
+            self.user_data.update(nmod);
+        }
+        self.write_user_data();
+    }
+
+    /// a file that failed to load is left alone, changes are only kept until the manager closes
+    fn write_user_data(&mut self) {
+        if self.user_data_failed {
+            let message = format!("{USER_DATA_FILE} couldn't be loaded, so tags and overrides aren't saved. Fix or remove it and reload to save them");
+            // only one banner however many changes are made
+            if !self.errors.iter().any(|e| e.1 == message) {
+                self.create_banner(Severity::Error, message);
+            }
+            return;
+        }
+        if let Err(e) = self.user_data.save(Path::new(USER_DATA_FILE)) {
+            self.create_banner(Severity::Error, format_error(&e));
+        }
+    }
+
+    /// failing this only loses manager preferences, so it gets a banner rather than a popup
+    fn save_state(&mut self) {
+        if let Err(e) = self
+            .state
+            .save(Path::new(STATE_FILE))
+            .context("Saving manager state")
+        {
+            self.create_banner(Severity::Error, format_error(&e));
+        }
+    }
+
+    /// writes the pack to the modpacks dir, replacing any loaded pack with the same file name
+    fn save_modpack(&mut self, pack: ModPack) -> anyhow::Result<()> {
+        let path = Path::new("./modpacks/").join(pack.file_name());
+        pack.save(
+            BufWriter::new(
+                File::create(path).context(format!("Creating modpack {}", pack.name()))?,
+            ),
+            self.state.pack_compression,
+        )
+        .context(format!("Saving modpack {}", pack.name()))?;
+        if let Some(found) = self
+            .pack_config
+            .modpacks
+            .iter_mut()
+            .find(|e| e.file_name() == pack.file_name())
+        {
+            *found = pack;
+        } else {
+            self.pack_config.modpacks.push(pack);
+        }
+        Ok(())
+    }
+
+    /// copies a modpack dropped onto the window into the modpacks dir, the file is kept as is so its compression doesn't change
+    fn import_dropped_pack(&mut self, file: &DroppedFile) -> anyhow::Result<()> {
+        let (content, file_name) = match (&file.path, &file.bytes) {
+            (Some(path), _) => (
+                fs::read(path).context(format!("Reading dropped file {}", path.display()))?,
+                path.file_name()
+                    .and_then(|e| e.to_str())
+                    .context(format!("Getting file name {}", path.display()))?
+                    .to_owned(),
+            ),
+            (None, Some(bytes)) => (bytes.to_vec(), file.name.clone()),
+            (None, None) => bail!("Dropped file {} has no contents", file.name),
+        };
+        let pack = ModPack::load(content.as_slice(), file_name.clone())
+            .context(format!("Loading dropped modpack {file_name}"))?;
+        let path = Path::new("./modpacks/").join(&file_name);
+        if path.exists() {
+            bail!("A modpack file called {file_name} already exists, rename one of them first");
+        }
+        fs::write(&path, &content).context(format!("Copying modpack to {}", path.display()))?;
+        for problem in pack.validate() {
+            self.create_banner(
+                Severity::Warning,
+                format!("Modpack {}: {problem}", pack.name()),
+            );
+        }
+        self.create_banner(Severity::Info, format!("Imported modpack {}", pack.name()));
+        self.pack_config.modpacks.push(pack);
+        Ok(())
+    }
+
+    fn import_dropped_files(&mut self, ctx: &egui::Context) {
+        if ctx.input(|i| !i.raw.hovered_files.is_empty()) {
+            let painter = ctx.layer_painter(LayerId::new(Order::Foreground, Id::new("Drop Hint")));
+            let screen = ctx.screen_rect();
+            painter.rect_filled(screen, 0.0, Color32::from_black_alpha(160));
+            painter.text(
+                screen.center(),
+                Align2::CENTER_CENTER,
+                "Drop to import modpacks",
+                TextStyle::Heading.resolve(&ctx.style()),
+                Color32::WHITE,
+            );
+        }
+        for file in ctx.input_mut(|i| std::mem::take(&mut i.raw.dropped_files)) {
+            let res = self.import_dropped_pack(&file);
+            self.result_popup(res);
+        }
+    }
+
+    fn render_mod_settings_panel(&mut self, ui: &mut Ui) -> anyhow::Result<()> {
+        let output = restorable_scroll_area(
+            "Mod Settings Scroll",
+            self.scroll.settings,
+            self.restore_scroll,
+        )
+        .show(ui, |ui| {
+            self.list_config
+                .mod_settings
+                .render(ui, &mut self.list_config.settings_filter)
+        });
+        self.scroll.settings = output.state.offset.y;
+        output.inner
+    }
+
+    fn render_mods_panel(&mut self, ui: &mut Ui) {
+        self.render_banners(ui);
+        if self.row_rect == None {
+            if let Some(nmod) = self.list_config.mods.get_mut(0) {
+                self.row_rect = Some(
+                    nmod.render(
+                        ui,
+                        self.init_errored,
+                        self.state.compact,
+                        self.state.show_ids,
+                    )
+                    .full_rect,
+                );
+                ui.ctx().request_discard("Measuring mod rows");
+            }
+        }
+
+        if let Some(edited) = self.list_config.search_edited {
+            let waited = edited.elapsed();
+            if waited >= SEARCH_DEBOUNCE {
+                self.list_config.applied_search = self.list_config.search.clone();
+                self.list_config.search_edited = None;
+            } else {
+                ui.ctx().request_repaint_after(SEARCH_DEBOUNCE - waited);
+            }
+        }
+
+        if self.list_config.parsed_search.source() != self.list_config.applied_search
+            || self.list_config.parsed_search.enabled_only() != self.state.enabled_only
+        {
+            let parsed = ParsedSearch::new(&self.list_config.applied_search);
+            self.list_config.parsed_search = if self.state.enabled_only {
+                parsed.with_enabled_only()
+            } else {
+                parsed
+            };
+        }
+        // taken so the list can be borrowed mutably while filtering, put back at the end
+        let search = std::mem::take(&mut self.list_config.parsed_search);
+        let broken_terms = &search.broken_terms;
+        ui.horizontal(|ui| {
+            ui.label("Search");
+            if ui
+                .text_edit_singleline(&mut self.list_config.search)
+                .on_hover_text(Condition::special_terms())
+                .changed()
+            {
+                self.list_config.search_edited = Some(Instant::now());
+            }
+            if ui
+                .toggle_value(&mut self.state.enabled_only, "Enabled only")
+                .on_hover_text("Only list enabled mods, like adding #enabled to the search")
+                .changed()
+            {
+                self.save_state();
+            }
+            if !broken_terms.is_empty() {
+                ui.label("Broken search terms: ");
+                broken_terms.iter().for_each(|(term, reason)| {
+                    ui.label(term.as_str()).on_hover_text(reason.to_string());
+                });
+            }
+        });
+        let (save, set_all, display_changed) = ui
+            .horizontal(|ui| {
+                let save = ui
+                    .add_enabled(!self.init_errored, Button::new("Save"))
+                    .on_hover_text(
+                        "Save mod config for use in game (requires restarting Noita), also Ctrl+S",
+                    )
+                    .on_disabled_hover_text(SAVE_DISABLED_REASON)
+                    .clicked();
+                let mut set_all = None;
+                if ui
+                    .add_enabled(!self.init_errored, Button::new("Enable all"))
+                    .on_hover_text("Enable every normal mod the search shows")
+                    .on_disabled_hover_text("The mod list failed to load")
+                    .clicked()
+                {
+                    set_all = Some(true);
+                }
+                if ui
+                    .add_enabled(!self.init_errored, Button::new("Disable all"))
+                    .on_hover_text("Disable every normal mod the search shows")
+                    .on_disabled_hover_text("The mod list failed to load")
+                    .clicked()
+                {
+                    set_all = Some(false);
                 }
-                Ok(())
+                let auto_save_changed = ui
+                    .checkbox(&mut self.state.auto_save, "Auto-save")
+                    .on_hover_text("Save a few seconds after the load order changes, this writes the game's files often")
+                    .changed();
+                if let Some(saved) = self.last_auto_save {
+                    let shown = saved.elapsed();
+                    if shown < SAVED_INDICATOR_TIME {
+                        ui.weak("✔ Saved");
+                        ui.ctx()
+                            .request_repaint_after(SAVED_INDICATOR_TIME.saturating_sub(shown));
+                    }
+                }
+                let sources_changed = ui
+                    .menu_button("Sources", |ui| {
+                        let changed = ui
+                            .checkbox(&mut self.state.load_local, "Local mods")
+                            .changed()
+                            | ui.checkbox(&mut self.state.load_workshop, "Workshop mods")
+                                .changed();
+                        ui.weak("Takes effect on reload, mods that aren't loaded keep their place in the config");
+                        if ui
+                            .button("Reload")
+                            .on_hover_text("Load everything from disk again, unsaved changes are lost")
+                            .clicked()
+                        {
+                            self.reload();
+                            ui.close_menu();
+                        }
+                        if ui
+                            .add_enabled(
+                                self.workshop_dir.is_some(),
+                                Button::new("Find incomplete workshop mods"),
+                            )
+                            .on_hover_text("List workshop folders without a mod.xml, which never show up in the list")
+                            .on_disabled_hover_text("No workshop directory was found")
+                            .clicked()
+                        {
+                            let res = self.find_incomplete_dirs();
+                            self.result_popup(res);
+                            ui.close_menu();
+                        }
+                        changed
+                    })
+                    .inner
+                    .unwrap_or(false);
+                if ui
+                    .button("About")
+                    .on_hover_text("Version, paths in use and how long loading took")
+                    .clicked()
+                {
+                    self.about_open = true;
+                }
+                if ui
+                    .button("Check conflicts")
+                    .on_hover_text("Look for enabled mods that might not work together")
+                    .clicked()
+                    && !self.report_conflicts()
+                {
+                    self.create_banner(Severity::Info, "No likely conflicts found".to_owned());
+                }
+                if ui
+                    .button("Copy load order")
+                    .on_hover_text("Copy a numbered list of the enabled mods for sharing, named after the modpack name box")
+                    .clicked()
+                {
+                    let name = &self.pack_config.name;
+                    ui.ctx().copy_text(load_order::report(
+                        &self.list_config.mods,
+                        self.state.report_workshop_ids,
+                        (!name.is_empty()).then_some(name.as_str()),
+                    ));
+                }
+                if ui
+                    .button("Import load order")
+                    .on_hover_text("Paste a list of mods to enable in order")
+                    .clicked()
+                {
+                    self.import_text.get_or_insert_with(String::new);
+                }
+                if ui
+                    .button("Backups")
+                    .on_hover_text("Restore an earlier copy of the game's mod config")
+                    .clicked()
+                {
+                    match backup::list(Path::new(BACKUP_DIR)).context("Listing backups") {
+                        Ok(backups) => self.backups = Some(backups),
+                        Err(e) => self.create_error(e),
+                    }
+                }
+                let display_changed = ui
+                    .checkbox(&mut self.state.report_workshop_ids, "Workshop ids")
+                    .on_hover_text(
+                        "Include the workshop ids of steam mods in the copied load order",
+                    )
+                    .changed()
+                    | ui.checkbox(&mut self.state.show_hidden, "Show hidden")
+                        .on_hover_text("Show mods hidden from their right click menu")
+                        .changed()
+                    | ui.checkbox(&mut self.state.tint_enabled, "Mark enabled")
+                        .on_hover_text("Mark enabled mods with a green edge")
+                        .changed();
+                if ui
+                    .selectable_label(self.state.settings_panel_open, "Settings")
+                    .on_hover_text(format!(
+                        "Show or hide the mod settings panel, also {}",
+                        ui.ctx().format_shortcut(&SETTINGS_PANEL_SHORTCUT)
+                    ))
+                    .clicked()
+                {
+                    self.toggle_settings_panel();
+                }
+                let layout_changed = egui::ComboBox::from_id_salt("Startup Layout")
+                    .selected_text(format!("Start: {}", self.state.startup_layout.label()))
+                    .show_ui(ui, |ui| {
+                        let mut changed = false;
+                        for layout in StartupLayout::ALL {
+                            changed |= ui
+                                .selectable_value(
+                                    &mut self.state.startup_layout,
+                                    layout,
+                                    layout.label(),
+                                )
+                                .on_hover_text(layout.description())
+                                .changed();
+                        }
+                        changed
+                    })
+                    .inner
+                    .unwrap_or(false);
+                let compact_changed = ui
+                    .checkbox(&mut self.state.compact, "Compact")
+                    .on_hover_text("Smaller rows without the source and kind icons, which are shown on hover instead")
+                    .changed();
+                if compact_changed {
+                    // rows are a different height now
+                    self.row_rect = None;
+                }
+                let ids_changed = ui
+                    .checkbox(&mut self.state.show_ids, "Show ids")
+                    .on_hover_text("Show each mod's id after its name, the mod config lists mods by id")
+                    .changed();
+                let display_changed = display_changed
+                    | compact_changed
+                    | ids_changed
+                    | layout_changed
+                    | sources_changed
+                    | auto_save_changed;
+                (save, set_all, display_changed)
             })
-            .inner
+            .inner;
+        if save {
+            self.save_from_ui();
+        }
+        if display_changed {
+            self.save_state();
+        }
+        if !self.list_config.selected.is_empty() {
+            self.render_batch_toolbar(ui);
+        }
+
+        // taken for the frame so the context doesn't borrow `self`, nothing drawn below changes the settings
+        let with_settings = std::mem::take(&mut self.list_config.mod_settings.mod_ids);
+        let context = self.search_context(&with_settings);
+        if let Some(enabled) = set_all {
+            self.list_config
+                .set_shown_enabled(&search, &context, enabled);
+        }
+        if !self.list_config.mods.is_empty()
+            && !self
+                .list_config
+                .mods
+                .iter()
+                .any(|x| search.matches(x, &context))
+        {
+            // an empty list looks broken, so say why it's empty
+            ui.vertical_centered(|ui| {
+                ui.add_space(20.0);
+                ui.label("No mods match your search");
+                if !search.broken_terms.is_empty() {
+                    ui.weak("Some search terms are broken, check them next to the search box");
+                }
+            });
+        }
+        let highlighted = std::mem::take(&mut self.pack_config.highlighted);
+        self.scroll.mods =
+            restorable_scroll_area("Mod List Scroll", self.scroll.mods, self.restore_scroll)
+                .show(ui, |ui| {
+                    self.render_dnd_modlist(ui, &search, &context, &highlighted)
+                })
+                .state
+                .offset
+                .y;
+        self.list_config.mod_settings.mod_ids = with_settings;
+        self.list_config.parsed_search = search;
+    }
+
+    /// loads the pack at the inspect window's path, it doesn't need to be in the modpacks dir
+    fn inspect_pack(inspect: &mut InspectPack) -> anyhow::Result<()> {
+        let path = PathBuf::from(inspect.path.trim().trim_matches('"'));
+        let file_name = path
+            .file_name()
+            .and_then(|e| e.to_str())
+            .context(format!("Getting file name of {}", path.display()))?
+            .to_owned();
+        let file = File::open(&path).context(format!("Opening modpack {}", path.display()))?;
+        let pack = ModPack::load(BufReader::new(file), file_name)
+            .context(format!("Inspecting modpack {}", path.display()))?;
+        let mut settings = pack.settings().clone();
+        settings.grouped = ModSettings::compute_grouped(&settings.values);
+        // everything in a pack was included when it was exported
+        settings.grouped.include_all(true);
+        inspect.settings = settings;
+        inspect.pack = Some(pack);
+        Ok(())
+    }
+
+    fn render_inspect_window(&mut self, ctx: &egui::Context) {
+        let Some(inspect) = &mut self.inspect else {
+            return;
+        };
+        let mut open = true;
+        let mut load = false;
+        let installed = &self.pack_config.installed_mods;
+        Window::new("Inspect pack")
+            .default_width(400.0)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.horizontal(|ui| {
+                    ui.label("Path");
+                    let response = ui.text_edit_singleline(&mut inspect.path);
+                    load = ui.button("Load").clicked()
+                        || (response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter)));
+                });
+                let Some(pack) = &inspect.pack else {
+                    ui.weak("Read only, the pack isn't imported and nothing is applied");
+                    return;
+                };
+                ui.heading(pack.name());
+                ui.weak(format!("{} (schema v{})", pack.file_name(), pack.version()));
+                ui.separator();
+                ui.label(format!("{} mods", pack.mods().len()));
+                ScrollArea::vertical()
+                    .id_salt("Inspect Mods")
+                    .max_height(200.0)
+                    .show(ui, |ui| {
+                        for (i, id) in pack.mods().iter().enumerate() {
+                            ui.horizontal(|ui| {
+                                ui.label(format!("{}. {id}", i + 1));
+                                if !installed.contains(id) {
+                                    ui.colored_label(YELLOW, "not installed");
+                                }
+                            });
+                        }
+                    });
+                ui.separator();
+                if pack.mods_only() {
+                    ui.label("Mods only, no settings");
+                    return;
+                }
+                ScrollArea::vertical()
+                    .id_salt("Inspect Settings")
+                    .max_height(300.0)
+                    .show(ui, |ui| {
+                        ui.disable();
+                        if inspect.settings.is_empty() {
+                            ui.label("No mod settings");
+                        }
+                        inspect.settings.grouped.render(ui, "", "", &mut Vec::new());
+                    });
+            });
+        if load {
+            let res = Self::inspect_pack(inspect);
+            self.result_popup(res);
+        } else if !open {
+            self.inspect = None;
+        }
+    }
+
+    /// the window for pasting a load order into, shown while `import_text` is `Some`
+    fn render_import_window(&mut self, ctx: &egui::Context) {
+        let Some(text) = &mut self.import_text else {
+            return;
+        };
+        let mut open = true;
+        let mut import = false;
+        Window::new("Import load order")
+            .default_width(400.0)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label("One mod id or name per line, a copied load order works too");
+                ui.weak("Lines starting with # are comments, # name: sets the modpack name");
+                ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    ui.add(TextEdit::multiline(text).desired_width(f32::INFINITY));
+                });
+                import = ui
+                    .button("Import")
+                    .on_hover_text("Enable the listed mods in this order and disable the rest")
+                    .clicked();
+            });
+        if import {
+            let text = self.import_text.take().unwrap_or_default();
+            self.import_load_order(&text);
+        } else if !open {
+            self.import_text = None;
+        }
+    }
+
+    fn render_about_window(&mut self, ctx: &egui::Context) {
+        let mut open = self.about_open;
+        Window::new("About").open(&mut open).show(ctx, |ui| {
+            ui.label(self.about_text());
+            ui.separator();
+            if ui
+                .button("Reset layout")
+                .on_hover_text(
+                    "Forget the manager's preferences, window size, panel sizes and zoom",
+                )
+                .clicked()
+            {
+                self.reset_layout = true;
+            }
+        });
+        self.about_open &= open;
+    }
+
+    /// puts the window back how it was on first start, the state file is rewritten with defaults
+    /// when the manager last ran is kept, otherwise every workshop mod would show as updated next time
+    fn reset_layout(&mut self, ctx: &egui::Context) {
+        self.state = PersistentState {
+            last_run: self.state.last_run,
+            ..Default::default()
+        };
+        self.save_state();
+        // egui keeps panel sizes, window positions and the zoom here, and the style along with them
+        ctx.memory_mut(|memory| *memory = Default::default());
+        apply_style(ctx);
+        ctx.send_viewport_cmd(ViewportCommand::InnerSize(WINDOW_SIZE.into()));
+        self.pending_layout = Some(self.state.startup_layout);
+        self.row_rect = None;
+    }
+
+    fn render_backups_window(&mut self, ctx: &egui::Context) {
+        let Some(backups) = &self.backups else {
+            return;
+        };
+        let mut open = true;
+        let mut restore = None;
+        let now = SystemTime::now();
+        Window::new("Restore backup")
+            .default_width(300.0)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                ui.label(format!(
+                    "Taken on startup and before saving, the newest {} are kept",
+                    self.state.config_backups
+                ));
+                if backups.is_empty() {
+                    ui.weak("No backups yet");
+                }
+                ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for backup in backups {
+                        ui.horizontal(|ui| {
+                            if ui
+                                .button("Restore")
+                                .on_hover_text(backup.path.display().to_string())
+                                .clicked()
+                            {
+                                restore = Some(backup.clone());
+                            }
+                            ui.label(backup.age(now));
+                        });
+                    }
+                });
+            });
+        if let Some(backup) = restore {
+            self.backups = None;
+            let res = self.restore_backup(&backup, now);
+            self.result_popup(res);
+        } else if !open {
+            self.backups = None;
+        }
+    }
+
+    fn find_incomplete_dirs(&mut self) -> anyhow::Result<()> {
+        let dir = self.workshop_dir.context("No workshop directory")?;
+        self.incomplete_dirs = Some(
+            loader::incomplete_workshop_dirs(dir).context("Finding incomplete workshop mods")?,
+        );
+        self.confirm_delete_dirs = false;
+        Ok(())
+    }
+
+    /// only lists them unless deleting is confirmed, steam downloads them again if still subscribed
+    fn render_incomplete_window(&mut self, ctx: &egui::Context) {
+        let Some(dirs) = &self.incomplete_dirs else {
+            return;
+        };
+        let mut open = true;
+        let mut delete = false;
+        Window::new("Incomplete workshop mods")
+            .default_width(400.0)
+            .open(&mut open)
+            .show(ctx, |ui| {
+                if dirs.is_empty() {
+                    ui.label("Every workshop folder has a mod.xml");
+                    return;
+                }
+                ui.label("These folders have no mod.xml, so they aren't loaded");
+                ScrollArea::vertical().max_height(300.0).show(ui, |ui| {
+                    for dir in dirs {
+                        ui.label(dir.display().to_string());
+                    }
+                });
+                if self.confirm_delete_dirs {
+                    ui.colored_label(
+                        ui.visuals().warn_fg_color,
+                        format!("Delete {} folders? This can't be undone", dirs.len()),
+                    );
+                    ui.horizontal(|ui| {
+                        delete = ui.button("Delete").clicked();
+                        if ui.button("Cancel").clicked() {
+                            self.confirm_delete_dirs = false;
+                        }
+                    });
+                } else if ui
+                    .button("Delete these folders")
+                    .on_hover_text("Steam downloads them again if you are still subscribed")
+                    .clicked()
+                {
+                    self.confirm_delete_dirs = true;
+                }
+            });
+        if delete {
+            let dirs = self.incomplete_dirs.take().unwrap_or_default();
+            let mut failed = Vec::new();
+            let mut deleted = 0;
+            let mut finished = 0;
+            for dir in dirs.iter() {
+                // steam may have finished the download while the window was open
+                if dir.join("mod.xml").is_file() {
+                    finished += 1;
+                    continue;
+                }
+                match fs::remove_dir_all(dir) {
+                    Ok(()) => deleted += 1,
+                    Err(e) => failed.push(format!("{}: {e}", dir.display())),
+                }
+            }
+            if failed.is_empty() {
+                let mut message = format!("Deleted {deleted} incomplete workshop folders");
+                if finished > 0 {
+                    message += &format!(", kept {finished} that finished downloading");
+                }
+                self.create_banner(Severity::Info, message);
+            } else {
+                self.create_popup(
+                    "Error",
+                    "Couldn't delete some folders:\n".to_owned() + &failed.join("\n"),
+                );
+            }
+            let res = self.find_incomplete_dirs();
+            self.result_popup(res);
+        } else if !open {
+            self.incomplete_dirs = None;
+        }
+    }
+
+    /// the current config is backed up first, so restoring can be undone
+    fn restore_backup(&mut self, backup: &backup::Backup, now: SystemTime) -> anyhow::Result<()> {
+        backup::create(
+            self.mod_config,
+            Path::new(BACKUP_DIR),
+            self.state.config_backups,
+        )
+        .context("Backing up mod config before restoring")?;
+        fs::copy(&backup.path, self.mod_config).context(format!(
+            "Restoring backup {} to {}",
+            backup.path.display(),
+            self.mod_config.display()
+        ))?;
+        self.create_banner(
+            Severity::Info,
+            format!("Restored the mod config from {}", backup.age(now)),
+        );
+        self.reload();
+        Ok(())
+    }
+
+    /// lines that don't match an installed mod are reported in a banner rather than stopping the import
+    /// a `# name:` comment fills in the modpack name box, ready for saving the order as a pack
+    fn import_load_order(&mut self, text: &str) {
+        let load_order::ParsedOrder { ids, unknown, name } =
+            load_order::parse(&self.list_config.mods, text);
+        if let Some(name) = name {
+            self.pack_config.name = name;
+        }
+        // a pack without settings reorders the list the same way applying a real one does
+        let pack = ModPack::new(String::new(), String::new(), &ids, &ModSettings::default());
+        pack.apply(
+            &mut self.list_config,
+            &self.pack_config.installed_mods,
+            ModApplyMode::Replace,
+            SettingsApplyMode::ModsOnly,
+        );
+        if !unknown.is_empty() {
+            self.create_banner(
+                Severity::Warning,
+                "Unknown mods in the imported load order:\n".to_owned() + &unknown.join("\n"),
+            );
+        }
+    }
+
+    /// saves the sizes of the resizable panels once the user lets go of them, so the layout survives restarts
+    /// `settings_width` is `None` while the settings panel is collapsed
+    fn remember_panel_sizes(
+        &mut self,
+        ctx: &egui::Context,
+        settings_width: Option<f32>,
+        modpack_height: f32,
+    ) {
+        if ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        let changed =
+            |old: Option<f32>, new: f32| !matches!(old, Some(old) if (old - new).abs() <= 0.5);
+        // a collapsed settings panel keeps the width it had
+        let settings_width = settings_width.or(self.state.settings_panel_width);
+        if settings_width.is_some_and(|width| changed(self.state.settings_panel_width, width))
+            || changed(self.state.modpack_panel_height, modpack_height)
+        {
+            self.state.settings_panel_width = settings_width;
+            self.state.modpack_panel_height = Some(modpack_height);
+            self.save_state();
+        }
     }
 
-    fn render_mod_settings_panel(&mut self, ui: &mut Ui) {
-        egui::ScrollArea::vertical()
-            .auto_shrink(false)
-            .show(ui, |ui| {
-                self.list_config.mod_settings.render(ui);
-            });
+    fn toggle_settings_panel(&mut self) {
+        self.state.settings_panel_open = !self.state.settings_panel_open;
+        self.save_state();
     }
 
-    fn render_mods_panel(&mut self, ui: &mut Ui) {
-        if self.row_rect == None {
-            if let Some(nmod) = self.list_config.mods.get_mut(0) {
-                self.row_rect = Some(nmod.render(ui, self.init_errored).full_rect);
-                ui.ctx().request_repaint();
-            }
+    /// shows [`conflicts::scan`] as a banner, returns if there were any
+    fn report_conflicts(&mut self) -> bool {
+        let conflicts = conflicts::scan(&self.list_config.mods);
+        if conflicts.is_empty() {
+            return false;
         }
+        self.create_banner(
+            Severity::Warning,
+            "These enabled mods might conflict, this is only a guess:\n".to_owned()
+                + &conflicts.join("\n"),
+        );
+        true
+    }
 
-        let cur_search = self.list_config.search.clone();
-        let conditions_err: Vec<_> = cur_search
-            .split(" ")
-            .map(|x| (x, Condition::new(x)))
-            .filter(|x| x.0 != "")
-            .collect();
-        let broken_terms: &Vec<_> = &conditions_err
-            .iter()
-            .filter(|x| x.1.is_none())
-            .map(|x| x.0)
-            .collect();
-        let conditions: &Vec<_> = &conditions_err.iter().filter_map(|x| x.1.clone()).collect();
-        ui.horizontal(|ui| {
-            ui.label("Search");
-            ui.text_edit_singleline(&mut self.list_config.search)
-                .on_hover_text(Condition::special_terms());
-            if !broken_terms.is_empty() {
-                ui.label("Broken search terms: ");
-                broken_terms.iter().for_each(|x| {
-                    ui.label(x.to_string());
-                });
-            }
-        });
-        if ui
-            .add_enabled(!self.init_errored, Button::new("Save"))
-            .on_hover_text("Save mod config for use in game (requires restarting Noita)")
-            .on_disabled_hover_text("Cannot save when there was an error starting the mod manager, fix the errors then save.")
-            .clicked()
-        {
-             let res = self.save_mods().context("While saving mod config") ;
-             self.result_popup(res);
+    /// what the Save button and shortcut do, errors are shown as popups
+    fn save_from_ui(&mut self) {
+        if self.init_errored {
+            self.create_error(anyhow!(SAVE_DISABLED_REASON));
+            return;
+        }
+        let res = self.save_mods().context("While saving mod config");
+        if res.is_ok() {
+            self.saved_fingerprint = self.mods_fingerprint();
+        }
+        self.result_popup(res);
+    }
+
+    /// a hash of the load order and what is enabled, which is what auto-save watches
+    fn mods_fingerprint(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        for nmod in self.list_config.mods.iter() {
+            nmod.id.hash(&mut hasher);
+            matches!(nmod.kind, ModKind::Normal(NormalMod { enabled: true })).hash(&mut hasher);
+            nmod.source.workshop_item_id().hash(&mut hasher);
         }
+        hasher.finish()
+    }
 
-        egui::ScrollArea::vertical()
-            .auto_shrink(false)
-            .show(ui, |ui| self.render_dnd_modlist(ui, conditions));
+    /// saves once the load order has stopped changing for [`AUTO_SAVE_DEBOUNCE`], if auto-save is on
+    fn auto_save(&mut self, ctx: &egui::Context) {
+        if !self.state.auto_save || self.init_errored || self.loading.is_some() {
+            return;
+        }
+        let fingerprint = self.mods_fingerprint();
+        if fingerprint == self.saved_fingerprint {
+            self.pending_save = None;
+            return;
+        }
+        let changed_at = match self.pending_save {
+            Some((pending, changed_at)) if pending == fingerprint => changed_at,
+            _ => {
+                self.pending_save = Some((fingerprint, Instant::now()));
+                Instant::now()
+            }
+        };
+        let waited = changed_at.elapsed();
+        if waited < AUTO_SAVE_DEBOUNCE {
+            ctx.request_repaint_after(AUTO_SAVE_DEBOUNCE - waited);
+            return;
+        }
+        // the order changes every frame while dragging, letting go repaints so there's no need to poll
+        if ctx.input(|i| i.pointer.any_down()) {
+            return;
+        }
+        // marked saved even if it failed, so a broken file doesn't get retried every frame
+        self.saved_fingerprint = fingerprint;
+        self.pending_save = None;
+        match self.save_mods().context("Auto-saving mod config") {
+            Ok(()) => self.last_auto_save = Some(Instant::now()),
+            Err(e) => self.create_banner(Severity::Error, format_error(&e)),
+        }
     }
 
     fn result_popup<T>(&mut self, error: anyhow::Result<T>) {
@@ -255,45 +1661,151 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
         }
     }
 
+    fn create_banner(&mut self, severity: Severity, message: String) {
+        println!("{severity:?}: {message}");
+        self.errors.push((severity, message));
+    }
+
+    fn render_banners(&mut self, ui: &mut Ui) {
+        let mut dismissed = None;
+        for (i, (severity, message)) in self.errors.iter().enumerate() {
+            Frame::new()
+                .fill(severity.color())
+                .inner_margin(4.0)
+                .corner_radius(2.0)
+                .show(ui, |ui| {
+                    ui.set_width(ui.available_width());
+                    ui.horizontal(|ui| {
+                        if ui.small_button("✖").on_hover_text("Dismiss").clicked() {
+                            dismissed = Some(i);
+                        }
+                        ui.label(message);
+                    });
+                });
+        }
+        if let Some(i) = dismissed {
+            self.errors.remove(i);
+        }
+    }
+
     fn create_error(&mut self, error: anyhow::Error) {
-        println!("Error: {error:?}");
+        let content = format_error(&error);
+        println!("Error: {content}");
+        self.create_popup("Error", content);
+    }
+
+    fn create_popup(&mut self, title: &'e str, content: String) {
         self.popups.push(Popup {
-            title: "Error",
-            content: format!("{error:?}"),
+            title,
+            content,
             id: self.global_id,
         });
         self.global_id += 1;
     }
 
-    fn load_modpacks(&mut self, dir: &Path) -> anyhow::Result<()> {
-        let mut packs = Vec::new();
-        for file in fs::read_dir(dir).context(format!("Reading modpack dir {}", dir.display()))? {
-            let file = file.context(format!("Accessing file for modpack dir {}", dir.display()))?;
-            let file_name = file
-                .file_name()
-                .to_str()
-                .context(format!("Getting file name {}", file.path().display()))?
-                .to_string();
-            if file_name.starts_with('.') {
-                continue;
-            }
-            let reader = BufReader::new(
-                File::open(file.path())
-                    .context(format!("Opening modpack file {}", file.path().display()))?,
+    /// version, paths and how long loading took, for working out why startup is slow
+    fn about_text(&self) -> String {
+        let path = |path: Option<&Path>| {
+            path.map(|e| e.display().to_string())
+                .unwrap_or("none".to_owned())
+        };
+        let mut out = format!(
+            "Noita Mod Manager {}\n\nMod config: {}\nMod settings: {}\nMods: {}\nWorkshop: {}\n",
+            env!("CARGO_PKG_VERSION"),
+            self.mod_config.display(),
+            self.mod_settings_file.display(),
+            path(self.mods_dir),
+            path(self.workshop_dir),
+        );
+        if let Some(stats) = &self.load_stats {
+            out += &format!(
+                "\nLoaded {} mods, {} settings and {} modpacks in {:.1?}\n",
+                stats.mods,
+                stats.settings,
+                stats.modpacks,
+                stats.total()
             );
-            let pack = ModPack::load(reader, file_name).context(format!(
-                "Loading modpack from file {}",
-                file.path().display()
-            ))?;
-            packs.push(pack);
+            for (step, time) in stats.steps.iter() {
+                out += &format!("  {step}: {time:.1?}\n");
+            }
         }
-        self.pack_config.modpacks = packs;
-        Ok(())
+        if let Some(compression) = &self.list_config.mod_settings.compression {
+            out += &format!("\nMod settings file: {}\n", compression.describe());
+        }
+        out
+    }
+
+    /// actions on every mod picked with ctrl click
+    fn render_batch_toolbar(&mut self, ui: &mut Ui) {
+        ui.horizontal(|ui| {
+            ui.label(format!("{} selected", self.list_config.selected.len()));
+            if ui.button("Enable").clicked() {
+                self.list_config.set_selected_enabled(true);
+            }
+            if ui.button("Disable").clicked() {
+                self.list_config.set_selected_enabled(false);
+            }
+            if ui
+                .button("Move to top")
+                .on_hover_text("Load the selected mods first")
+                .clicked()
+            {
+                self.list_config.move_selected(true);
+            }
+            if ui
+                .button("Move to bottom")
+                .on_hover_text("Load the selected mods last")
+                .clicked()
+            {
+                self.list_config.move_selected(false);
+            }
+            ui.add(TextEdit::singleline(&mut self.batch_tag).desired_width(80.0 * SCALE));
+            if ui.button("Add tag").clicked() {
+                let mut changed = false;
+                for nmod in self.list_config.mods.iter_mut() {
+                    if self.list_config.selected.contains(&nmod.id) && nmod.add_tag(&self.batch_tag)
+                    {
+                        self.user_data.update(nmod);
+                        changed = true;
+                    }
+                }
+                self.batch_tag.clear();
+                if changed {
+                    self.write_user_data();
+                }
+            }
+            if ui
+                .button("Clear")
+                .on_hover_text("Deselect everything, ctrl click mods to select them")
+                .clicked()
+            {
+                self.list_config.selected.clear();
+            }
+        });
     }
 
-    fn render_dnd_modlist(&mut self, ui: &mut Ui, conditions: &[Condition]) {
-        let payload = egui::DragAndDrop::take_payload::<DNDPayload>(ui.ctx()); // taking the payload clears it
-        let inner_response = self.render_modlist(ui, conditions, payload.is_some());
+    /// what searching needs besides the search itself, see [`SearchContext`]
+    /// `with_settings` is [`ModSettings::mod_ids`]
+    fn search_context<'a>(&self, with_settings: &'a HashSet<String>) -> SearchContext<'a> {
+        SearchContext {
+            show_hidden: self.state.show_hidden,
+            with_settings: Some(with_settings),
+            recent_since: SystemTime::now().checked_sub(Duration::from_secs(
+                self.state.recent_days.saturating_mul(24 * 60 * 60),
+            )),
+        }
+    }
+
+    fn render_dnd_modlist(
+        &mut self,
+        ui: &mut Ui,
+        search: &ParsedSearch,
+        context: &SearchContext,
+        highlighted: &HashSet<String>,
+    ) {
+        let payload = take_payload_of_type::<DNDPayload>(ui.ctx()); // taking the payload clears it
+        let inner_response =
+            self.render_modlist(ui, search, context, highlighted, payload.is_some());
 
         if ui.ctx().input(|i| i.pointer.any_down()) {
             return;
@@ -311,7 +1823,7 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
                     .mods
                     .iter()
                     .enumerate()
-                    .filter(|(_, e)| e.matches(conditions))
+                    .filter(|(_, e)| search.matches(e, context))
                     .collect::<Vec<_>>();
                 let mut target_mod_idx = if to_idx == 0 {
                     // if we drag it to the start always put it at the start
@@ -348,14 +1860,32 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
     fn render_modlist(
         &mut self,
         ui: &mut Ui,
-        conditions: &[Condition],
+        search: &ParsedSearch,
+        context: &SearchContext,
+        highlighted: &HashSet<String>,
         do_dnd: bool,
     ) -> InnerResponse<Option<usize>> {
-        ui.scope(|ui| {
-            self.list_config
+        let mut edited = None;
+        // the list isn't in load order when sorted, so dragging would be confusing
+        let can_drag = !self.init_errored && !search.reorders();
+        let response = ui.scope(|ui| {
+            if self.state.compact {
+                ui.spacing_mut().item_spacing.y = 1.0;
+            }
+            let mut shown = self
+                .list_config
                 .mods
                 .iter_mut()
-                .filter(|x| x.matches(conditions))
+                .filter(|x| search.matches(x, context))
+                .collect::<Vec<_>>();
+            // stable sorts, so equal mods stay in load order
+            if let Some(sort) = search.sort {
+                shown.sort_by(|a, b| sort.compare(a, b));
+            } else if search.is_ranked() {
+                shown.sort_by_key(|e| Reverse(search.score(e)));
+            }
+            shown
+                .into_iter()
                 .enumerate()
                 .map(|(i, nmod)| {
                     let id = Id::new(("Modlist DND", i));
@@ -368,15 +1898,42 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
                         cursor.max.y = cursor.min.y + self.row_rect.unwrap().height();
                         painter.rect_filled(cursor, 0.0, ui.visuals().faint_bg_color);
                     }
+                    if highlighted.contains(&nmod.id) {
+                        let mut row = ui.cursor();
+                        row.max.y = row.min.y + self.row_rect.unwrap().height();
+                        ui.painter().rect_filled(row, 0.0, PACK_HIGHLIGHT);
+                    }
+                    if self.list_config.selected.contains(&nmod.id) {
+                        let mut row = ui.cursor();
+                        row.max.y = row.min.y + self.row_rect.unwrap().height();
+                        ui.painter().rect_filled(
+                            row,
+                            0.0,
+                            ui.visuals().selection.bg_fill.gamma_multiply(0.4),
+                        );
+                    }
+                    if self.state.tint_enabled
+                        && matches!(nmod.kind, ModKind::Normal(NormalMod { enabled: true }))
+                    {
+                        let mut edge = ui.cursor();
+                        edge.max.y = edge.min.y + self.row_rect.unwrap().height();
+                        edge.max.x = edge.min.x + ENABLED_TINT_WIDTH;
+                        ui.painter().rect_filled(edge, 0.0, ENABLED_TINT);
+                    }
 
                     // largely pilfered from Ui::dnd_drag_source
-                    if ui.ctx().is_being_dragged(id) && !self.init_errored {
+                    if ui.ctx().is_being_dragged(id) && can_drag {
                         DragAndDrop::set_payload(ui.ctx(), payload);
 
                         let layer_id = LayerId::new(Order::Tooltip, id);
                         let response = ui
                             .scope_builder(UiBuilder::new().layer_id(layer_id), |ui| {
-                                nmod.render(ui, self.init_errored)
+                                nmod.render(
+                                    ui,
+                                    self.init_errored,
+                                    self.state.compact,
+                                    self.state.show_ids,
+                                )
                             })
                             .response;
 
@@ -389,15 +1946,35 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
                         }
                         None
                     } else {
-                        let scoped = ui.scope(|ui| nmod.render(ui, self.init_errored));
+                        let scoped = ui.scope(|ui| {
+                            nmod.render(
+                                ui,
+                                self.init_errored,
+                                self.state.compact,
+                                self.state.show_ids,
+                            )
+                        });
                         let inner = scoped.inner;
-                        ui.interact(inner.text_rect, id, Sense::drag())
-                            .on_hover_cursor(if self.init_errored {
-                                egui::CursorIcon::NotAllowed
-                            } else {
+                        // click as well so the context menu can open
+                        let response = ui
+                            .interact(inner.text_rect, id, Sense::click_and_drag())
+                            .on_hover_cursor(if can_drag {
                                 egui::CursorIcon::Grab
+                            } else {
+                                egui::CursorIcon::NotAllowed
                             })
-                            .on_hover_text(inner.text_hover);
+                            .on_hover_ui(|ui| nmod.render_hover(ui, self.state.compact));
+                        if response.clicked()
+                            && ui.input(|i| i.modifiers.command)
+                            && !self.list_config.selected.remove(&nmod.id)
+                        {
+                            self.list_config.selected.insert(nmod.id.clone());
+                        }
+                        response.context_menu(|ui| {
+                            if nmod.render_user_menu(ui, &mut self.new_tag) {
+                                edited = Some(nmod.id.clone());
+                            }
+                        });
                         if do_dnd && scoped.response.contains_pointer() {
                             if let Some(pointer) = ui.input(|i| i.pointer.interact_pos()) {
                                 let rect = scoped.response.rect;
@@ -422,237 +1999,11 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
                     }
                 })
                 .fold(None, |acc, e| if acc.is_some() { acc } else { e })
-        })
-    }
-
-    /// call this to sort the loaded mods by a config, must have loaded some mods for this to do anything
-    fn sort_mods(mods: &[Mod], mod_config: &Vec<ModConfigItem>) -> anyhow::Result<Vec<Mod>> {
-        let mut mod_map = HashMap::new();
-        for nmod in mods.iter() {
-            if mod_map.insert(nmod.id.clone(), nmod).is_some() {
-                bail!(
-                    "Duplicate mod id {} in loaded mods, mod list is broken",
-                    &nmod.id
-                );
-            }
-        }
-
-        let mut new_mods = Vec::new();
-        for config_item in mod_config.iter() {
-            if let Some(got_mod) = mod_map.get(&config_item.id) {
-                let mod_enabled = if let ModKind::Normal(normal_mod) = &got_mod.kind {
-                    let mut new_mod = (*got_mod).clone();
-                    let mut new_kind = *normal_mod;
-                    new_kind.enabled = config_item.enabled;
-                    new_mod.kind = ModKind::Normal(new_kind);
-                    new_mod
-                } else {
-                    (*got_mod).clone()
-                };
-                new_mods.push(mod_enabled);
-            }
-        }
-
-        Ok(new_mods)
-    }
-
-    fn parse_config_item(node: &XMLNode) -> anyhow::Result<ModConfigItem> {
-        let element = node
-            .as_element()
-            .context("Couldn't convert xmlnode to element?")?;
-        let name = element.attributes.get("name").context("Missing name")?;
-        let enabled = element
-            .attributes
-            .get("enabled")
-            .context("Missing enabled")?
-            == "1";
-        Ok(ModConfigItem {
-            id: name.clone(),
-            enabled,
-        })
-    }
-
-    fn parse_config<R: Read>(src: R) -> anyhow::Result<Vec<ModConfigItem>> {
-        let tree = Element::parse(src)?;
-        tree.children
-            .iter()
-            .map(|x| Self::parse_config_item(x))
-            .try_fold(Vec::new(), |mut acc, x| {
-                acc.push(x?);
-                Ok(acc)
-            })
-    }
-
-    fn load_mod(path: &Path, is_workshop: bool) -> anyhow::Result<Option<Mod>> {
-        let mod_xml = path.join("mod.xml");
-        if !mod_xml.is_file() {
-            return Ok(None);
-        }
-
-        let file = File::open(mod_xml).context("Opening mod xml")?;
-        let reader = BufReader::new(file);
-        // TODO: port NXML to rust and use it here
-        let tree = Element::parse(reader).context("Parsing mod xml")?;
-        fn get(tree: &Element, key: String, default: String) -> String {
-            if let Some(x) = tree.attributes.get(&key) {
-                x.to_string()
-            } else {
-                default
-            }
-        }
-
-        let suffix = if let Some(x) = path.file_name() {
-            x.to_string_lossy().to_string()
-        } else {
-            bail!("Path doesn't have a filename???")
-        };
-        let mut id = suffix.clone();
-
-        let source = if is_workshop {
-            id = "".to_owned();
-            File::open(path.join("mod_id.txt"))
-                .context(format!("Opening mod_id.txt for {suffix}"))?
-                .read_to_string(&mut id)
-                .context(format!("Reading mod_id.txt for {suffix}"))?;
-            ModSource::Steam(SteamMod {
-                workshop_id: suffix.clone(),
-            })
-        } else if path.join(".git").is_dir() {
-            let repo = git2::Repository::discover(path).context("Finding git repo")?;
-            let remotes = repo.remotes().context("Getting git remotes")?;
-            let remote = repo
-                .find_remote("origin")
-                .ok()
-                .map(|x| x.url().map(|x| x.to_owned()))
-                .flatten()
-                .or(remotes.get(0).map(|x| x.to_owned()));
-            let host = if let Some(url) = &remote {
-                if url.contains("github") {
-                    GitHost::Github
-                } else if url.contains("gitlab") {
-                    GitHost::Gitlab
-                } else {
-                    GitHost::Other
-                }
-            } else {
-                GitHost::Other
-            };
-            ModSource::Git(GitMod { remote, host })
-        } else {
-            ModSource::Manual
-        };
-
-        let mut tags = None;
-        if let Ok(workshop) = File::open(path.join("workshop.xml")) {
-            let reader = BufReader::new(workshop);
-            let xml = Element::parse(reader).context("Parsing workshop.xml")?;
-            let tags_str = get(&xml, "tags".to_owned(), "".to_owned());
-            if tags_str != "" {
-                // if it's default the mod doesn't support tags
-                tags = Some(tags_str.split(',').map(|e| e.trim().to_owned()).collect());
-            }
-        }
-
-        let nmod = Mod {
-            source,
-            tags,
-            id,
-            kind: if get(&tree, "is_translation".to_owned(), "0".to_owned()) == "1" {
-                ModKind::Translation
-            } else if get(&tree, "is_game_mode".to_owned(), "0".to_owned()) == "1" {
-                ModKind::Gamemode
-            } else {
-                ModKind::Normal(NormalMod { enabled: false })
-            },
-            settings_fold_open: get(&tree, "settings_fold_open".to_string(), "0".to_owned()) == "1",
-            name: get(&tree, "name".to_owned(), "unnamed".to_owned()),
-            description: get(&tree, "description".to_owned(), "".to_owned()).replace("\\n", "\n"),
-            unsafe_api: get(
-                &tree,
-                "request_no_api_restrictions".to_owned(),
-                "0".to_owned(),
-            ) == "1",
-        };
-        Ok(Some(nmod))
-    }
-
-    fn load_dir(dir: &Path, is_workshop: bool) -> anyhow::Result<Vec<Mod>> {
-        let mut mods = Vec::new();
-        fs::read_dir(dir)
-            .context("Reading mods directory")?
-            .try_for_each::<_, anyhow::Result<()>>(|item| {
-                let item = item.context("Getting directory item")?;
-                let path = item.path();
-                if !path.is_dir() {
-                    return Ok(());
-                }
-                let nmod = Self::load_mod(&path, is_workshop).context({
-                    format!(
-                        "Loading mod with path {}",
-                        path.to_str()
-                            .context("Producing a path string from a Path")?
-                    )
-                })?;
-                {
-                    if let Some(x) = nmod {
-                        mods.push(x);
-                    }
-                    Ok(())
-                }
-            })?;
-        Ok(mods)
-    }
-
-    fn init(&mut self) -> anyhow::Result<()> {
-        let mut mods = Vec::new();
-        if let Some(dir) = self.mods_dir {
-            mods.extend(
-                Self::load_dir(dir, false)
-                    .context(format!("Loading mods dir {}", dir.display()))?,
-            );
-        }
-        if let Some(dir) = self.workshop_dir {
-            mods.extend(
-                Self::load_dir(dir, true)
-                    .context(format!("Loading workshop mods dir {}", dir.display()))?,
-            );
+        });
+        if let Some(id) = edited {
+            self.save_user_data(&id);
         }
-
-        let config = Self::parse_config(BufReader::new(
-            File::open(self.mod_config)
-                .context(format!("Opening mod config {}", self.mod_config.display()))?,
-        ))
-        .context(format!("Parsing mod config {}", self.mod_config.display()))?;
-        self.list_config.mods = Self::sort_mods(&mods, &config).context("Sorting mods")?;
-
-        let file = BufReader::new(File::open(self.mod_settings_file).context(format!(
-            "Opening mod settings {}",
-            self.mod_settings_file.display()
-        ))?);
-        self.list_config.mod_settings = ModSettings::load(
-            file,
-            fs::metadata(self.mod_settings_file)
-                .context(format!(
-                    "Getting metadata for mod settings {}",
-                    self.mod_settings_file.display()
-                ))?
-                .len() as usize,
-        )
-        .context(format!(
-            "Loading mod settings {}",
-            self.mod_settings_file.display()
-        ))?;
-        self.load_modpacks(Path::new("./modpacks/"))
-            .context("Loading modpacks")?;
-        // mod_settings.save(BufWriter::new(File::create("./saved_settings")?))?;
-        let installed = self
-            .list_config
-            .mods
-            .iter()
-            .map(|e| e.id.clone())
-            .collect::<HashSet<_>>();
-        self.pack_config.installed_mods = installed;
-        Ok(())
+        response
     }
 
     pub fn new(
@@ -666,13 +2017,20 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
             mod_config,
             list_config: ModListConfig {
                 search: "".to_owned(),
+                applied_search: "".to_owned(),
+                search_edited: None,
+                parsed_search: Default::default(),
                 mods: Vec::new(),
                 mod_settings: Default::default(),
+                selected: HashSet::new(),
+                unloaded: Vec::new(),
+                settings_filter: String::new(),
             },
             mods_dir,
             workshop_dir,
             mod_settings_file: mod_settings,
             popups: Vec::new(),
+            errors: Vec::new(),
             global_id: 0,
             row_rect: None,
             pack_config: ModPackConfig {
@@ -680,20 +2038,215 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
                 modpacks: Vec::new(),
                 row_rect: None,
                 installed_mods: HashSet::new(),
+                highlighted: HashSet::new(),
             },
             init_errored: false,
+            settings_failed: false,
+            pending_layout: None,
+            load_stats: None,
+            sizes: None,
+            saved_fingerprint: 0,
+            pending_save: None,
+            last_auto_save: None,
+            state: Default::default(),
+            user_data: Default::default(),
+            user_data_failed: false,
+            new_tag: String::new(),
+            import_text: None,
+            inspect: None,
+            backups: None,
+            about_open: false,
+            reset_layout: false,
+            restore_session_order: false,
+            last_run: None,
+            incomplete_dirs: None,
+            confirm_delete_dirs: false,
+            batch_tag: String::new(),
+            loading: None,
+            scroll: Default::default(),
+            restore_scroll: false,
             profiler,
         })
     }
 
-    pub fn run(mut self) -> anyhow::Result<()> {
-        if let Err(e) = self.init() {
-            self.create_error(e);
-            self.init_errored = true;
+    /// throws away what was loaded and loads it all again, for picking up changes on disk or to [`PersistentState::load_local`]
+    fn reload(&mut self) {
+        self.init_errored = false;
+        self.row_rect = None;
+        self.list_config.selected.clear();
+        self.start_loading();
+    }
+
+    fn start_loading(&mut self) {
+        let paths = LoadPaths {
+            mod_config: self.mod_config.to_path_buf(),
+            mods_dir: self
+                .mods_dir
+                .filter(|_| self.state.load_local)
+                .map(Path::to_path_buf),
+            workshop_dir: self
+                .workshop_dir
+                .filter(|_| self.state.load_workshop)
+                .map(Path::to_path_buf),
+            mod_settings_file: self.mod_settings_file.to_path_buf(),
+            user_data_file: Path::new(USER_DATA_FILE).to_path_buf(),
+        };
+        let (sender, receiver) = mpsc::channel();
+        thread::spawn(move || loader::load_in_background(paths, sender));
+        self.loading = Some(Loading {
+            receiver,
+            loaded: 0,
+            total: 0,
+        });
+    }
+
+    /// takes any progress from the loading thread, swapping in the loaded data once it's done
+    fn poll_loading(&mut self) {
+        let Some(loading) = &mut self.loading else {
+            return;
+        };
+        let result = loop {
+            match loading.receiver.try_recv() {
+                Ok(LoadMessage::Progress { loaded, total }) => {
+                    loading.loaded = loaded;
+                    loading.total = total;
+                }
+                Ok(LoadMessage::Done(result)) => break result,
+                Err(TryRecvError::Empty) => return,
+                Err(TryRecvError::Disconnected) => {
+                    break Err(anyhow!("Loading thread exited without a result"))
+                }
+            }
+        };
+        self.loading = None;
+        let restore = std::mem::take(&mut self.restore_session_order);
+        match result {
+            Ok(mut data) => {
+                for nmod in data.mods.iter_mut() {
+                    nmod.mark_updated(self.last_run);
+                }
+                // a reload keeps what was picked for export, keys that are gone are just dropped
+                let included = self.list_config.mod_settings.grouped.to_set();
+                data.mod_settings
+                    .grouped
+                    .apply_set(&included, "".to_owned());
+                self.list_config.mods = data.mods;
+                self.list_config.unloaded = data.unloaded;
+                self.list_config.mod_settings = data.mod_settings;
+                self.pack_config.modpacks = data.modpacks;
+                self.pack_config.installed_mods = data.installed_mods;
+                self.user_data = data.user_data;
+                self.user_data_failed = data.user_data_failed;
+                self.settings_failed = data.settings_failed;
+                self.load_stats = Some(data.stats);
+                self.sizes = Some(data.sizes);
+                self.saved_fingerprint = self.mods_fingerprint();
+                if restore {
+                    self.restore_session_order();
+                }
+                self.restore_scroll = true;
+                for warning in data.warnings {
+                    self.create_banner(Severity::Warning, warning);
+                }
+                self.report_conflicts();
+            }
+            Err(e) => {
+                self.create_error(e);
+                self.init_errored = true;
+            }
+        }
+    }
+
+    /// puts back the unsaved order the manager was closed with, it still has to be saved for the game to use it
+    fn restore_session_order(&mut self) {
+        match session_order::load(Path::new(SESSION_ORDER_FILE)) {
+            Ok(Some(order)) => {
+                if session_order::restore(&mut self.list_config.mods, &order) {
+                    self.create_banner(
+                        Severity::Info,
+                        "Restored the unsaved load order from last time, save to use it in game"
+                            .to_owned(),
+                    );
+                }
+            }
+            Ok(None) => {}
+            Err(e) => self.create_banner(Severity::Warning, format_error(&e)),
+        }
+    }
+
+    /// keeps an unsaved load order for next time, or forgets the old one once the game's config has it
+    fn save_session_order(&self) -> anyhow::Result<()> {
+        if self.init_errored || self.loading.is_some() {
+            return Ok(());
+        }
+        let path = Path::new(SESSION_ORDER_FILE);
+        if self.mods_fingerprint() == self.saved_fingerprint {
+            session_order::clear(path)
+        } else {
+            session_order::save(path, &self.list_config.mods)
+        }
+    }
+
+    /// takes any mod sizes the loading thread has measured since last frame
+    fn poll_sizes(&mut self, ctx: &egui::Context) {
+        let Some(sizes) = &self.sizes else {
+            return;
+        };
+        loop {
+            match sizes.try_recv() {
+                Ok((id, workshop_item_id, bytes)) => {
+                    if let Some(nmod) = self.list_config.mods.iter_mut().find(|e| e.id == id) {
+                        nmod.set_size(&workshop_item_id, bytes);
+                    }
+                }
+                Err(TryRecvError::Empty) => {
+                    // same as loading, the thread can't wake us up
+                    ctx.request_repaint_after(Duration::from_millis(200));
+                    return;
+                }
+                Err(TryRecvError::Disconnected) => {
+                    self.sizes = None;
+                    return;
+                }
+            }
+        }
+    }
+
+    /// `layout` overrides the saved [`StartupLayout`] for this run
+    pub fn run(mut self, layout: Option<StartupLayout>) -> anyhow::Result<()> {
+        match PersistentState::load(Path::new(STATE_FILE)) {
+            Ok(state) => self.state = state,
+            Err(e) => self.create_banner(
+                Severity::Error,
+                format_error(&e.context("Loading manager state")),
+            ),
+        }
+        self.pending_layout = Some(layout.unwrap_or(self.state.startup_layout));
+        self.last_run = self
+            .state
+            .last_run
+            .map(|e| UNIX_EPOCH + Duration::from_secs(e));
+        self.state.last_run = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .ok()
+            .map(|e| e.as_secs());
+        self.save_state();
+        // what the game last wrote, before we get a chance to change it
+        if let Err(e) = backup::create(
+            self.mod_config,
+            Path::new(BACKUP_DIR),
+            self.state.config_backups,
+        ) {
+            self.create_banner(
+                Severity::Warning,
+                format_error(&e.context("Backing up mod config")),
+            );
         }
+        self.restore_session_order = true;
+        self.start_loading();
 
         let options = eframe::NativeOptions {
-            viewport: egui::ViewportBuilder::default().with_inner_size([320.0, 240.0]),
+            viewport: egui::ViewportBuilder::default().with_inner_size(WINDOW_SIZE),
             ..Default::default()
         };
         let result = eframe::run_native(
@@ -701,31 +2254,7 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
             options,
             Box::new(|cc| {
                 egui_extras::install_image_loaders(&cc.egui_ctx);
-                cc.egui_ctx.style_mut(|style| {
-                    style.text_styles.insert(
-                        TextStyle::Small,
-                        FontId::new(9.0 * SCALE, FontFamily::Proportional),
-                    );
-                    style.text_styles.insert(
-                        TextStyle::Body,
-                        FontId::new(12.5 * SCALE, FontFamily::Proportional),
-                    );
-                    style.text_styles.insert(
-                        TextStyle::Button,
-                        FontId::new(12.5 * SCALE, FontFamily::Proportional),
-                    );
-                    style.text_styles.insert(
-                        TextStyle::Heading,
-                        FontId::new(18.0 * SCALE, FontFamily::Proportional),
-                    );
-                    style.text_styles.insert(
-                        TextStyle::Monospace,
-                        FontId::new(12.0 * SCALE, FontFamily::Monospace),
-                    );
-                    style.spacing.interact_size *= SCALE;
-                    style.spacing.icon_width *= SCALE;
-                    style.spacing.icon_spacing *= SCALE;
-                });
+                apply_style(&cc.egui_ctx);
                 Ok(Box::new(self))
             }),
         );
@@ -733,37 +2262,62 @@ impl<'d, 'e, 'f> App<'d, 'e, 'f> {
         result.map_err(|x| anyhow!(format!("{x:?}")))
     }
 
-    fn save_mods(&self) -> anyhow::Result<()> {
-        let buf = "<Mods>\n".to_string()
-                    + &self
-                        .list_config.mods
-                        .iter()
-                        .map(|x| {
-                            let id = &x.id;
-                            let enabled = if let ModKind::Normal(normal_mod) = &x.kind {
-                                normal_mod.enabled as usize
-                            } else {
-                                0
-                            };
-                            let workshop_item_id = if let ModSource::Steam(steam_mod) = &x.source {
-                                &steam_mod.workshop_id
-                            } else {
-                                "0"
-                        };
-                            let settings_fold_open = x.settings_fold_open as usize;
-                            format!("\t<Mod enabled=\"{enabled}\" name=\"{id}\" settings_fold_open=\"{settings_fold_open}\" workshop_item_id=\"{workshop_item_id}\" />\n")
-                        })
-                        .reduce(|a, b| a + &b).unwrap_or("".to_owned()) + "</Mods>";
+    fn save_mods(&mut self) -> anyhow::Result<()> {
+        backup::create(
+            self.mod_config,
+            Path::new(BACKUP_DIR),
+            self.state.config_backups,
+        )
+        .context("Backing up mod config before saving")?;
+        let buf = config_xml(&self.list_config.mods, &self.list_config.unloaded);
         let mut file = File::create(self.mod_config).context("Opening mod config for saving")?;
         write!(file, "{}", buf).context("Writing to mod config")?;
         file.flush().context("Flushing config file")?;
-        let mut file = File::create(self.mod_settings_file).context("Opening mod setting for saving")?;
-        self.list_config.mod_settings.save(&mut file).context("Saving mod settings")?;
+        if self.settings_failed {
+            return Ok(());
+        }
+        let mut file =
+            File::create(self.mod_settings_file).context("Opening mod setting for saving")?;
+        let compression = self
+            .list_config
+            .mod_settings
+            .save(&mut file)
+            .context("Saving mod settings")?;
+        self.list_config.mod_settings.compression = Some(compression);
         file.flush().context("Flushing settings file")?;
         Ok(())
     }
 }
 
+/// the manager's text and spacing, scaled up from egui's defaults by [`SCALE`]
+fn apply_style(ctx: &egui::Context) {
+    ctx.style_mut(|style| {
+        style.text_styles.insert(
+            TextStyle::Small,
+            FontId::new(9.0 * SCALE, FontFamily::Proportional),
+        );
+        style.text_styles.insert(
+            TextStyle::Body,
+            FontId::new(12.5 * SCALE, FontFamily::Proportional),
+        );
+        style.text_styles.insert(
+            TextStyle::Button,
+            FontId::new(12.5 * SCALE, FontFamily::Proportional),
+        );
+        style.text_styles.insert(
+            TextStyle::Heading,
+            FontId::new(18.0 * SCALE, FontFamily::Proportional),
+        );
+        style.text_styles.insert(
+            TextStyle::Monospace,
+            FontId::new(12.0 * SCALE, FontFamily::Monospace),
+        );
+        style.spacing.interact_size *= SCALE;
+        style.spacing.icon_width *= SCALE;
+        style.spacing.icon_spacing *= SCALE;
+    });
+}
+
 pub trait UiSizedExt {
     fn fixed_size_group<F: FnOnce(&mut Self)>(&mut self, size: f32, f: F);
 }
@@ -778,10 +2332,27 @@ impl UiSizedExt for egui::Ui {
 }
 
 impl eframe::App for App<'_, '_, '_> {
+    fn on_exit(&mut self, _gl: Option<&eframe::glow::Context>) {
+        // the window is already gone so there's nowhere to show this
+        if let Err(e) = self.save_session_order() {
+            println!("Error: {}", format_error(&e));
+        }
+    }
+
     fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         #[cfg(feature = "profiler")]
         {
             self.profiler.frame_counter += 1;
+            self.profiler.window_frames += 1;
+            let window = self.profiler.window_start.elapsed();
+            if window >= FRAME_LOG_INTERVAL {
+                println!(
+                    "Profiler: {} frames in the last {:.1?}",
+                    self.profiler.window_frames, window
+                );
+                self.profiler.window_frames = 0;
+                self.profiler.window_start = Instant::now();
+            }
             if self.profiler.frame_counter % 1000 == 0 {
                 if let Ok(report) = self.profiler.profiler.report().build() {
                     let file = File::create("flamegraph.svg").unwrap();
@@ -790,18 +2361,139 @@ impl eframe::App for App<'_, '_, '_> {
             }
         }
 
-        self.popups.retain(|popup| popup.show(&ctx));
+        let mut index = 0;
+        self.popups.retain(|popup| {
+            index += 1;
+            popup.show(ctx, index - 1)
+        });
+
+        self.poll_loading();
+        self.poll_sizes(ctx);
+        if let Some(loading) = &self.loading {
+            egui::CentralPanel::default().show(ctx, |ui| {
+                ui.centered_and_justified(|ui| {
+                    ui.vertical_centered(|ui| {
+                        ui.heading(format!(
+                            "Loading mods… ({}/{})",
+                            loading.loaded, loading.total
+                        ));
+                        ui.add(ProgressBar::new(if loading.total == 0 {
+                            0.0
+                        } else {
+                            loading.loaded as f32 / loading.total as f32
+                        }));
+                    });
+                });
+            });
+            // progress comes from another thread which can't wake us up
+            ctx.request_repaint_after(Duration::from_millis(50));
+            return;
+        }
+
+        // a focused text field might want the keys for itself
+        if !ctx.wants_keyboard_input() && ctx.input_mut(|i| i.consume_shortcut(&SAVE_SHORTCUT)) {
+            self.save_from_ui();
+        }
+
+        if !ctx.wants_keyboard_input()
+            && ctx.input_mut(|i| i.consume_shortcut(&SETTINGS_PANEL_SHORTCUT))
+        {
+            self.toggle_settings_panel();
+        }
+
+        self.import_dropped_files(ctx);
+
+        if std::mem::take(&mut self.reset_layout) {
+            self.reset_layout(ctx);
+        }
+
+        match self.pending_layout.take() {
+            Some(StartupLayout::Mods) => self.state.settings_panel_open = false,
+            Some(StartupLayout::Settings) => {
+                self.state.settings_panel_open = true;
+                self.state.settings_panel_width = Some(ctx.screen_rect().width() * 0.6);
+            }
+            Some(StartupLayout::Remember) | None => {}
+        }
 
-        egui::SidePanel::right(Id::new("Right Panel")).show(ctx, |ui| {
-            self.render_mod_settings_panel(ui);
+        let mut settings_panel = egui::SidePanel::right(Id::new("Right Panel")).resizable(true);
+        if let Some(width) = self.state.settings_panel_width {
+            settings_panel = settings_panel.default_width(width);
+        }
+        // not animated, the in between widths would be remembered
+        let settings_width = self.state.settings_panel_open.then(|| {
+            settings_panel
+                .show(ctx, |ui| {
+                    let res = self.render_mod_settings_panel(ui);
+                    self.result_popup(res)
+                })
+                .response
+                .rect
+                .width()
         });
-        egui::TopBottomPanel::bottom(Id::new("Modpack Panel"))
-            .resizable(true)
+        let mut modpack_panel =
+            egui::TopBottomPanel::bottom(Id::new("Modpack Panel")).resizable(true);
+        if let Some(height) = self.state.modpack_panel_height {
+            modpack_panel = modpack_panel.default_height(height);
+        }
+        let modpack_height = modpack_panel
             .show(ctx, |ui| {
                 let res = self.render_modpack_panel(ui);
                 self.result_popup(res)
-            });
+            })
+            .response
+            .rect
+            .height();
+        self.remember_panel_sizes(ctx, settings_width, modpack_height);
 
         egui::CentralPanel::default().show(ctx, |ui| self.render_mods_panel(ui));
+        self.render_import_window(ctx);
+        self.render_inspect_window(ctx);
+        self.render_about_window(ctx);
+        self.render_backups_window(ctx);
+        self.render_incomplete_window(ctx);
+        self.auto_save(ctx);
+        self.restore_scroll = false;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::ModListConfig;
+    use crate::r#mod::{
+        conditional::{ParsedSearch, SearchContext},
+        Mod, ModKind, NormalMod,
+    };
+
+    fn enabled(mods: &[Mod]) -> Vec<&str> {
+        mods.iter()
+            .filter(|e| matches!(e.kind, ModKind::Normal(NormalMod { enabled: true })))
+            .map(|e| e.id.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn set_shown_enabled() {
+        let normal = ModKind::Normal(NormalMod { enabled: false });
+        let mut broken = Mod::test("spell_broken", normal);
+        broken.load_error = Some("Parsing mod xml".to_owned());
+        let mut list = ModListConfig {
+            mods: vec![
+                Mod::test("spell_lab", normal),
+                Mod::test("spell_translation", ModKind::Translation),
+                broken,
+                Mod::test("other", normal),
+            ],
+            ..Default::default()
+        };
+        let context = SearchContext::default();
+        list.set_shown_enabled(&ParsedSearch::new("spell"), &context, true);
+        assert_eq!(enabled(&list.mods), ["spell_lab"]);
+        assert!(matches!(list.mods[1].kind, ModKind::Translation));
+
+        list.set_shown_enabled(&ParsedSearch::new(""), &context, true);
+        assert_eq!(enabled(&list.mods), ["spell_lab", "other"]);
+        list.set_shown_enabled(&ParsedSearch::new("other"), &context, false);
+        assert_eq!(enabled(&list.mods), ["spell_lab"]);
     }
 }
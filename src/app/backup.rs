@@ -0,0 +1,120 @@
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use anyhow::Context;
+
+pub const BACKUP_DIR: &str = "./backups/";
+const PREFIX: &str = "mod_config_";
+
+/// A copy of the game's mod config, named by when it was taken
+#[derive(Clone, Debug)]
+pub struct Backup {
+    pub path: PathBuf,
+    /// milliseconds since the unix epoch
+    pub timestamp: u128,
+}
+
+impl Backup {
+    /// how long ago the backup was taken, like "5 minutes ago"
+    pub fn age(&self, now: SystemTime) -> String {
+        let now = now.duration_since(UNIX_EPOCH).map_or(0, |e| e.as_millis());
+        let secs = now.saturating_sub(self.timestamp) / 1000;
+        let (amount, unit) = match secs {
+            0..60 => return "just now".to_owned(),
+            60..3600 => (secs / 60, "minute"),
+            3600..86400 => (secs / 3600, "hour"),
+            _ => (secs / 86400, "day"),
+        };
+        format!("{amount} {unit}{} ago", if amount == 1 { "" } else { "s" })
+    }
+}
+
+/// newest first, files that don't look like backups are left out
+pub fn list(dir: &Path) -> anyhow::Result<Vec<Backup>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut backups = Vec::new();
+    for file in fs::read_dir(dir).context(format!("Reading backup dir {}", dir.display()))? {
+        let file = file.context(format!("Accessing file in backup dir {}", dir.display()))?;
+        let timestamp = file
+            .file_name()
+            .to_str()
+            .and_then(|e| e.strip_prefix(PREFIX))
+            .and_then(|e| e.strip_suffix(".xml"))
+            .and_then(|e| e.parse().ok());
+        if let Some(timestamp) = timestamp {
+            backups.push(Backup {
+                path: file.path(),
+                timestamp,
+            });
+        }
+    }
+    backups.sort_by_key(|e| std::cmp::Reverse(e.timestamp));
+    Ok(backups)
+}
+
+/// copies `config` into `dir` and removes all but the newest `keep` backups
+/// nothing is written if the newest backup is the same, so auto-save doesn't push out older ones
+pub fn create(config: &Path, dir: &Path, keep: usize) -> anyhow::Result<()> {
+    if keep == 0 || !config.exists() {
+        return Ok(());
+    }
+    let content = fs::read(config).context(format!("Reading mod config {}", config.display()))?;
+    let backups = list(dir)?;
+    if let Some(newest) = backups.first() {
+        if fs::read(&newest.path).is_ok_and(|e| e == content) {
+            return Ok(());
+        }
+    }
+    fs::create_dir_all(dir).context(format!("Creating backup dir {}", dir.display()))?;
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .context("Getting backup timestamp")?
+        .as_millis();
+    let path = dir.join(format!("{PREFIX}{timestamp}.xml"));
+    fs::write(&path, content).context(format!("Writing backup {}", path.display()))?;
+    // the new one isn't in `backups`, so one less old one is kept
+    for old in backups.iter().skip(keep - 1) {
+        fs::remove_file(&old.path)
+            .context(format!("Removing old backup {}", old.path.display()))?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{create, list};
+
+    #[test]
+    fn rolling_backups() {
+        let dir = std::env::temp_dir().join(format!("noita_modman_backups_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        let config = dir.join("mod_config.xml");
+        let backups = dir.join("backups");
+        fs::create_dir_all(&dir).expect("Creating test dir must work");
+        for i in 0..4 {
+            fs::write(&config, format!("<Mods>{i}</Mods>")).expect("Writing config must work");
+            create(&config, &backups, 3).expect("Backing up must work");
+            // the same contents again don't make another backup
+            create(&config, &backups, 3).expect("Backing up must work");
+            std::thread::sleep(std::time::Duration::from_millis(2));
+        }
+        let listed = list(&backups).expect("Listing must work");
+        assert_eq!(listed.len(), 3);
+        assert_eq!(
+            fs::read_to_string(&listed[0].path).expect("Reading backup must work"),
+            "<Mods>3</Mods>"
+        );
+        assert_eq!(
+            fs::read_to_string(&listed[2].path).expect("Reading backup must work"),
+            "<Mods>1</Mods>"
+        );
+        fs::remove_dir_all(&dir).expect("Cleaning up must work");
+    }
+}
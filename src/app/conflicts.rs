@@ -0,0 +1,75 @@
+use crate::r#mod::{Mod, ModKind, NormalMod};
+
+/// tags where having two enabled mods with it usually means they fight over the same thing
+const EXCLUSIVE_TAGS: [&str; 2] = ["gamemode", "overhaul"];
+
+/// whether `longer` looks like an addon or fork of `shorter`, like `spell_lab` and `spell_lab_shugged`
+fn shares_id_prefix(shorter: &str, longer: &str) -> bool {
+    longer
+        .strip_prefix(shorter)
+        .is_some_and(|rest| rest.starts_with(['_', '-']))
+}
+
+/// a guess at enabled mods that don't work together, one line per pair
+/// this is only a heuristic so it should never stop anything, just be shown to the user
+pub fn scan(mods: &[Mod]) -> Vec<String> {
+    let enabled = mods
+        .iter()
+        .filter(|e| matches!(e.kind, ModKind::Normal(NormalMod { enabled: true })))
+        .collect::<Vec<_>>();
+    let mut warnings = Vec::new();
+    for (i, a) in enabled.iter().enumerate() {
+        for b in enabled.iter().skip(i + 1) {
+            for tag in EXCLUSIVE_TAGS {
+                let has_tag = |nmod: &Mod| nmod.all_tags().any(|e| e.eq_ignore_ascii_case(tag));
+                if has_tag(a) && has_tag(b) {
+                    warnings.push(format!(
+                        "{} and {} are both tagged {tag}",
+                        a.display_name(),
+                        b.display_name()
+                    ));
+                }
+            }
+            if shares_id_prefix(&a.id, &b.id) || shares_id_prefix(&b.id, &a.id) {
+                warnings.push(format!(
+                    "{} and {} have similar ids ({} and {}), one may be a copy of the other",
+                    a.display_name(),
+                    b.display_name(),
+                    a.id,
+                    b.id
+                ));
+            }
+        }
+    }
+    warnings
+}
+
+#[cfg(test)]
+mod test {
+    use super::scan;
+    use crate::r#mod::{Mod, ModKind, NormalMod};
+
+    fn enabled_mod(id: &str, tags: &[&str]) -> Mod {
+        let mut nmod = Mod::test(id, ModKind::Normal(NormalMod { enabled: true }));
+        nmod.tags = Some(tags.iter().map(|e| e.to_string()).collect());
+        nmod
+    }
+
+    #[test]
+    fn conflicts() {
+        let mut disabled = enabled_mod("disabled", &["Gamemode"]);
+        disabled.kind = ModKind::Normal(NormalMod { enabled: false });
+        let mods = [
+            enabled_mod("arena", &["Gamemode"]),
+            enabled_mod("spell_lab", &["content"]),
+            enabled_mod("spell_lab_shugged", &["content"]),
+            enabled_mod("spellbound", &[]),
+            enabled_mod("roguelite", &["gamemode"]),
+            disabled,
+        ];
+        let warnings = scan(&mods);
+        assert_eq!(warnings.len(), 2, "{warnings:?}");
+        assert!(warnings[0].contains("arena") && warnings[0].contains("roguelite"));
+        assert!(warnings[1].contains("spell_lab_shugged"));
+    }
+}
@@ -0,0 +1,172 @@
+use std::collections::HashSet;
+
+use crate::r#mod::{Mod, ModKind, ModSource, NormalMod};
+
+/// the start of the first line of a [`report`], older reports didn't make it a comment so it is still skipped
+const REPORT_HEADER: &str = "Noita load order";
+/// a comment starting with this names the list, like `# name: Spell mods`
+const NAME_PREFIX: &str = "name:";
+
+/// a numbered plain text list of the enabled mods in load order, for pasting where people can read it
+/// `workshop_ids` adds the id of steam mods so others can find the same ones
+/// `name` is written as a comment which [`parse`] picks up again
+pub fn report(mods: &[Mod], workshop_ids: bool, name: Option<&str>) -> String {
+    let enabled = mods
+        .iter()
+        .filter(|e| matches!(e.kind, ModKind::Normal(NormalMod { enabled: true })))
+        .collect::<Vec<_>>();
+    let mut out = format!(
+        "# {REPORT_HEADER}, {} of {} mods enabled\n",
+        enabled.len(),
+        mods.len()
+    );
+    if let Some(name) = name {
+        out += &format!("# {NAME_PREFIX} {name}\n");
+    }
+    for (i, nmod) in enabled.iter().enumerate() {
+        let source = match &nmod.source {
+            ModSource::Steam(steam_mod) if workshop_ids => {
+                format!("Steam {}", steam_mod.workshop_id)
+            }
+            ModSource::Steam(_) => "Steam".to_owned(),
+            ModSource::Git(git_mod) => match &git_mod.remote {
+                Some(remote) => format!("Git {remote}"),
+                None => "Git".to_owned(),
+            },
+            ModSource::ModWorkshop(workshop_mod) => format!("ModWorkshop {}", workshop_mod.link),
+            ModSource::Manual => "Manual".to_owned(),
+        };
+        out += &format!(
+            "{}. {} ({}) - {source}\n",
+            i + 1,
+            nmod.display_name(),
+            nmod.id
+        );
+    }
+    out
+}
+
+/// the id a [`report`] line like `3. Name (id) - Steam` refers to, if the line looks like one
+fn report_line_id(line: &str) -> Option<&str> {
+    let (number, rest) = line.split_once(". ")?;
+    number.parse::<usize>().ok()?;
+    let (named, _source) = rest.rsplit_once(") - ")?;
+    Some(named.rsplit_once(" (")?.1)
+}
+
+/// What a pasted load order turned into
+#[derive(Debug, Default)]
+pub struct ParsedOrder {
+    /// in load order without duplicates
+    pub ids: Vec<String>,
+    /// lines that didn't match anything
+    pub unknown: Vec<String>,
+    /// from a `# name:` comment, the last one wins
+    pub name: Option<String>,
+}
+
+/// finds the mods a pasted list refers to, one per line, by exact id first then case insensitive name
+/// lines copied from a [`report`] work too, lines starting with `#` are comments
+pub fn parse(mods: &[Mod], text: &str) -> ParsedOrder {
+    let mut ids = Vec::new();
+    let mut seen = HashSet::new();
+    let mut unknown = Vec::new();
+    let mut name = None;
+    for line in text.lines().map(str::trim) {
+        if let Some(comment) = line.strip_prefix('#') {
+            let comment = comment.trim_start();
+            if comment
+                .get(..NAME_PREFIX.len())
+                .is_some_and(|e| e.eq_ignore_ascii_case(NAME_PREFIX))
+            {
+                name =
+                    Some(comment[NAME_PREFIX.len()..].trim().to_owned()).filter(|e| !e.is_empty());
+            }
+            continue;
+        }
+        if line.is_empty() || line.starts_with(REPORT_HEADER) {
+            continue;
+        }
+        let wanted = report_line_id(line).unwrap_or(line);
+        let found = mods.iter().find(|e| e.id == wanted).or_else(|| {
+            let lower = line.to_lowercase();
+            mods.iter().find(|e| {
+                e.name.to_lowercase() == lower || e.display_name().to_lowercase() == lower
+            })
+        });
+        match found {
+            Some(nmod) => {
+                if seen.insert(&nmod.id) {
+                    ids.push(nmod.id.clone());
+                }
+            }
+            None => unknown.push(line.to_owned()),
+        }
+    }
+    ParsedOrder { ids, unknown, name }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse, report};
+    use crate::r#mod::{Mod, ModKind, ModSource, NormalMod, SteamMod};
+
+    fn steam_mod(id: &str, enabled: bool) -> Mod {
+        let mut nmod = Mod::test(id, ModKind::Normal(NormalMod { enabled }));
+        nmod.source = ModSource::Steam(SteamMod {
+            workshop_id: format!("{id}_workshop"),
+        });
+        nmod.name = id.to_uppercase();
+        nmod
+    }
+
+    #[test]
+    fn report_lists_enabled_in_order() {
+        let mods = [
+            steam_mod("b", true),
+            steam_mod("skipped", false),
+            steam_mod("a", true),
+        ];
+        assert_eq!(
+            report(&mods, true, None),
+            "# Noita load order, 2 of 3 mods enabled\n1. B (b) - Steam b_workshop\n2. A (a) - Steam a_workshop\n"
+        );
+        assert!(!report(&mods, false, None).contains("workshop"));
+    }
+
+    #[test]
+    fn parse_matches_ids_names_and_reports() {
+        let mods = [steam_mod("a", true), steam_mod("b", false)];
+        let parsed = parse(&mods, "b\n\n  a  \nnot installed\nb");
+        assert_eq!(parsed.ids, ["b", "a"]);
+        assert_eq!(parsed.unknown, ["not installed"]);
+
+        let parsed = parse(&mods, "B\nA");
+        assert_eq!(parsed.ids, ["b", "a"]);
+        assert!(parsed.unknown.is_empty());
+
+        let parsed = parse(&mods, &report(&mods, true, None));
+        assert_eq!(parsed.ids, ["a"]);
+        assert!(parsed.unknown.is_empty());
+        assert_eq!(parsed.name, None);
+
+        // reports from before the header was a comment
+        let parsed = parse(&mods, "Noita load order, 1 of 2 mods enabled\na");
+        assert_eq!(parsed.ids, ["a"]);
+        assert!(parsed.unknown.is_empty());
+    }
+
+    #[test]
+    fn parse_comments() {
+        let mods = [steam_mod("a", true), steam_mod("b", false)];
+        let parsed = parse(&mods, "# my favourites\n#Name:  Spells \nb\n  # a\na");
+        assert_eq!(parsed.ids, ["b", "a"]);
+        assert!(parsed.unknown.is_empty());
+        assert_eq!(parsed.name.as_deref(), Some("Spells"));
+
+        let parsed = parse(&mods, &report(&mods, false, Some("Round trip")));
+        assert_eq!(parsed.ids, ["a"]);
+        assert!(parsed.unknown.is_empty());
+        assert_eq!(parsed.name.as_deref(), Some("Round trip"));
+    }
+}
@@ -0,0 +1,1078 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::{self, File},
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+    sync::mpsc::{self, Receiver, Sender},
+    time::{Duration, Instant},
+};
+
+use anyhow::{bail, Context};
+use xmltree::{Element, XMLNode};
+
+use super::{
+    format_error,
+    modpack::{self, modsettings::ModSettings, ModPack},
+    user_data::UserData,
+    ModConfigItem,
+};
+use crate::r#mod::{GitHost, GitMod, Mod, ModKind, ModSource, NormalMod, SteamMod, UNNAMED};
+
+/// Owned copies of the paths we load from, so loading can happen off the ui thread
+#[derive(Clone, Debug)]
+pub struct LoadPaths {
+    pub mod_config: PathBuf,
+    pub mods_dir: Option<PathBuf>,
+    pub workshop_dir: Option<PathBuf>,
+    pub mod_settings_file: PathBuf,
+    pub user_data_file: PathBuf,
+}
+
+/// config entries kept for a source that wasn't loaded, each with how many loaded mods come before it
+pub type KeptEntries = Vec<(usize, ModConfigItem)>;
+/// a mod's id, the workshop item id of the copy that was measured and its size in bytes
+pub type MeasuredSize = (String, String, u64);
+/// the same as [`MeasuredSize`] with the directory to measure instead of a size
+type CopyDir = (String, String, PathBuf);
+
+/// Everything `App` needs from disk before it can show the real panels
+pub struct LoadedData {
+    pub mods: Vec<Mod>,
+    pub mod_settings: ModSettings,
+    pub modpacks: Vec<ModPack>,
+    pub installed_mods: HashSet<String>,
+    pub user_data: UserData,
+    /// the user data file couldn't be read, `user_data` is empty and must not be saved over it
+    pub user_data_failed: bool,
+    /// the settings file couldn't be read, `mod_settings` is empty and must not be saved over it
+    pub settings_failed: bool,
+    /// config entries of sources that weren't loaded
+    pub unloaded: KeptEntries,
+    /// problems that didn't stop loading, like a modpack that couldn't be read
+    pub warnings: Vec<String>,
+    pub stats: LoadStats,
+    /// sent one at a time as they are measured
+    pub sizes: Receiver<MeasuredSize>,
+}
+
+/// How long each part of loading took and how much it loaded, for the about popup
+#[derive(Clone, Debug, Default)]
+pub struct LoadStats {
+    pub steps: Vec<(&'static str, Duration)>,
+    pub mods: usize,
+    pub settings: usize,
+    pub modpacks: usize,
+}
+
+impl LoadStats {
+    /// records the time since `start` as `step`, then restarts it for the next step
+    fn lap(&mut self, step: &'static str, start: &mut Instant) {
+        self.steps.push((step, start.elapsed()));
+        *start = Instant::now();
+    }
+
+    pub fn total(&self) -> Duration {
+        self.steps.iter().map(|e| e.1).sum()
+    }
+}
+
+pub enum LoadMessage {
+    Progress {
+        loaded: usize,
+        total: usize,
+    },
+    /// boxed as the data is much bigger than a progress message
+    Done(anyhow::Result<Box<LoadedData>>),
+}
+
+/// runs [`load`] and reports the result back over `sender`, meant to be the body of the loading thread
+/// mod sizes are slow to measure and only needed for searching, so they are sent over [`LoadedData::sizes`] after the rest
+pub fn load_in_background(paths: LoadPaths, sender: Sender<LoadMessage>) {
+    let (size_sender, sizes) = mpsc::channel();
+    // if the app has closed there is nobody to tell
+    match load(&paths, &sender, sizes) {
+        Ok((data, dirs)) => {
+            let _ = sender.send(LoadMessage::Done(Ok(Box::new(data))));
+            for (id, workshop_item_id, dir) in dirs {
+                if size_sender
+                    .send((id, workshop_item_id, dir_size(&dir)))
+                    .is_err()
+                {
+                    return;
+                }
+            }
+        }
+        Err(e) => {
+            let _ = sender.send(LoadMessage::Done(Err(e)));
+        }
+    }
+}
+
+/// total size of the files under `dir`, anything that can't be read counts as empty
+/// symlinks aren't followed so a link to a parent can't loop forever
+fn dir_size(dir: &Path) -> u64 {
+    let Ok(items) = fs::read_dir(dir) else {
+        return 0;
+    };
+    items
+        .filter_map(Result::ok)
+        .map(|item| match item.metadata() {
+            Ok(metadata) if metadata.is_dir() => dir_size(&item.path()),
+            Ok(metadata) => metadata.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
+
+/// also returns the directory of every copy of every mod, for measuring their sizes
+fn load(
+    paths: &LoadPaths,
+    sender: &Sender<LoadMessage>,
+    sizes: Receiver<MeasuredSize>,
+) -> anyhow::Result<(LoadedData, Vec<CopyDir>)> {
+    let mut stats = LoadStats::default();
+    let mut start = Instant::now();
+    let dirs = [(&paths.mods_dir, false), (&paths.workshop_dir, true)]
+        .into_iter()
+        .filter_map(|(dir, is_workshop)| dir.as_ref().map(|dir| (dir, is_workshop)))
+        .collect::<Vec<_>>();
+    let mut total = 0;
+    for (dir, _) in dirs.iter() {
+        total += count_dir(dir).context(format!("Counting mods in {}", dir.display()))?;
+    }
+    let mut loaded = 0;
+    let mut on_loaded = || {
+        loaded += 1;
+        let _ = sender.send(LoadMessage::Progress { loaded, total });
+    };
+
+    let mut warnings = Vec::new();
+    let mut mods = Vec::new();
+    let mut mod_dirs = Vec::new();
+    for (dir, is_workshop) in dirs.into_iter() {
+        let loaded =
+            load_dir(dir, is_workshop, &mut on_loaded, &mut warnings).context(if is_workshop {
+                format!("Loading workshop mods dir {}", dir.display())
+            } else {
+                format!("Loading mods dir {}", dir.display())
+            })?;
+        for (nmod, mod_dir) in loaded {
+            mod_dirs.push((
+                nmod.id.clone(),
+                nmod.source.workshop_item_id().to_owned(),
+                mod_dir,
+            ));
+            mods.push(nmod);
+        }
+    }
+    let mut mods = merge_duplicates(mods);
+    stats.lap("Loading mod dirs", &mut start);
+
+    let config = parse_config(
+        BufReader::new(
+            File::open(&paths.mod_config)
+                .context(format!("Opening mod config {}", paths.mod_config.display()))?,
+        ),
+        &mut warnings,
+    )
+    .context(format!("Parsing mod config {}", paths.mod_config.display()))?;
+    stats.lap("Parsing mod config", &mut start);
+    // before sorting, so a mod overridden to be normal gets its enabled state from the config
+    let (user_data, user_data_failed) = match UserData::load(&paths.user_data_file) {
+        Ok(user_data) => (user_data, false),
+        Err(e) => {
+            warnings.push(format!(
+                "Mod user data couldn't be loaded, tags and overrides won't be saved so the file is left alone: {}",
+                format_error(&e)
+            ));
+            (Default::default(), true)
+        }
+    };
+    user_data.attach(&mut mods);
+    stats.lap("Loading user data", &mut start);
+    // entries for a source we didn't load are kept as they are so saving doesn't lose them
+    let (mods, unloaded) = sort_mods(&mods, &config, |item| {
+        if item.workshop_item_id == "0" {
+            paths.mods_dir.is_none()
+        } else {
+            paths.workshop_dir.is_none()
+        }
+    })
+    .context("Sorting mods")?;
+    stats.lap("Sorting mods", &mut start);
+
+    let (mod_settings, settings_failed) = match load_settings(&paths.mod_settings_file) {
+        Ok(settings) => (settings, false),
+        Err(e) => {
+            warnings.push(format!(
+                "Mod settings couldn't be loaded, they won't be shown or saved so the file is left alone: {}",
+                format_error(&e)
+            ));
+            (Default::default(), true)
+        }
+    };
+    stats.lap("Loading mod settings", &mut start);
+    let modpacks_dir = Path::new("./modpacks/");
+    let mut modpacks = load_modpacks(modpacks_dir, &mut warnings).context("Loading modpacks")?;
+    match modpack::load_order(&modpacks_dir.join(modpack::ORDER_FILE)) {
+        Ok(order) => modpack::sort_by_order(&mut modpacks, &order),
+        Err(e) => warnings.push(format_error(&e)),
+    }
+    stats.lap("Loading modpacks", &mut start);
+    stats.mods = mods.len();
+    stats.settings = mod_settings.len();
+    stats.modpacks = modpacks.len();
+    let installed_mods = mods.iter().map(|e| e.id.clone()).collect::<HashSet<_>>();
+    let data = LoadedData {
+        mods,
+        mod_settings,
+        modpacks,
+        installed_mods,
+        user_data,
+        user_data_failed,
+        settings_failed,
+        unloaded,
+        warnings,
+        stats,
+        sizes,
+    };
+    Ok((data, mod_dirs))
+}
+
+fn load_settings(path: &Path) -> anyhow::Result<ModSettings> {
+    let file = BufReader::new(
+        File::open(path).context(format!("Opening mod settings {}", path.display()))?,
+    );
+    ModSettings::load(
+        file,
+        fs::metadata(path)
+            .context(format!(
+                "Getting metadata for mod settings {}",
+                path.display()
+            ))?
+            .len() as usize,
+    )
+    .context(format!("Loading mod settings {}", path.display()))
+}
+
+/// number of directories in `dir`, which is how many times [`load_dir`] will report progress
+fn count_dir(dir: &Path) -> anyhow::Result<usize> {
+    let mut count = 0;
+    for item in fs::read_dir(dir).context("Reading mods directory")? {
+        if item.context("Getting directory item")?.path().is_dir() {
+            count += 1;
+        }
+    }
+    Ok(count)
+}
+
+/// a pack that fails to load is skipped and added to `warnings`, only failing to read the dir is an error
+fn load_modpacks(dir: &Path, warnings: &mut Vec<String>) -> anyhow::Result<Vec<ModPack>> {
+    let mut packs = Vec::new();
+    for file in fs::read_dir(dir).context(format!("Reading modpack dir {}", dir.display()))? {
+        let file = file.context(format!("Accessing file for modpack dir {}", dir.display()))?;
+        let file_name = file
+            .file_name()
+            .to_str()
+            .context(format!("Getting file name {}", file.path().display()))?
+            .to_string();
+        if file_name.starts_with('.') {
+            continue;
+        }
+        let pack = File::open(file.path())
+            .context(format!("Opening modpack file {}", file.path().display()))
+            .and_then(|file| ModPack::load(BufReader::new(file), file_name))
+            .context(format!(
+                "Loading modpack from file {}",
+                file.path().display()
+            ));
+        match pack {
+            Ok(pack) => {
+                for problem in pack.validate() {
+                    warnings.push(format!("Modpack {}: {problem}", pack.name()));
+                }
+                packs.push(pack)
+            }
+            Err(e) => warnings.push(format!("Skipped a modpack: {}", format_error(&e))),
+        }
+    }
+    Ok(packs)
+}
+
+/// copies of a mod in both the mods dir and the workshop share an id, so they become one mod
+/// the first copy is kept and the rest go in [`Mod::other_copies`]
+fn merge_duplicates(mods: Vec<Mod>) -> Vec<Mod> {
+    let mut merged: Vec<Mod> = Vec::new();
+    let mut positions = HashMap::<String, usize>::new();
+    for nmod in mods {
+        match positions.get(&nmod.id) {
+            Some(&i) => merged[i].other_copies.push(nmod.into()),
+            None => {
+                positions.insert(nmod.id.clone(), merged.len());
+                merged.push(nmod);
+            }
+        }
+    }
+    merged
+}
+
+/// call this to sort the loaded mods by a config, must have loaded some mods for this to do anything
+/// config entries without a loaded mod are dropped unless `keep` says otherwise, kept ones are returned with how many loaded mods came before them
+/// loaded mods that aren't in the config yet go at the end, disabled and by id, so new installs show up before the game has seen them
+pub(crate) fn sort_mods(
+    mods: &[Mod],
+    mod_config: &Vec<ModConfigItem>,
+    keep: impl Fn(&ModConfigItem) -> bool,
+) -> anyhow::Result<(Vec<Mod>, KeptEntries)> {
+    let mut mod_map = HashMap::new();
+    for nmod in mods.iter() {
+        if mod_map.insert(nmod.id.clone(), nmod).is_some() {
+            bail!(
+                "Duplicate mod id {} in loaded mods, mod list is broken",
+                &nmod.id
+            );
+        }
+    }
+
+    let mut new_mods = Vec::new();
+    let mut kept = Vec::new();
+    // the config lists a mod installed twice once per copy, the first line picks the copy and the rest are dropped
+    let mut placed = HashSet::new();
+    for config_item in mod_config.iter() {
+        if let Some(got_mod) = mod_map.get(&config_item.id) {
+            if !placed.insert(config_item.id.as_str()) {
+                continue;
+            }
+            let mut new_mod = (*got_mod).clone();
+            if let ModKind::Normal(normal_mod) = &mut new_mod.kind {
+                normal_mod.enabled = config_item.enabled;
+            }
+            // a mod installed twice is one entry, the config says which copy the game loaded
+            if let Some(i) = new_mod
+                .other_copies
+                .iter()
+                .position(|e| e.source.workshop_item_id() == config_item.workshop_item_id)
+            {
+                new_mod.use_copy(i);
+            }
+            new_mods.push(new_mod);
+        } else if keep(config_item) {
+            kept.push((new_mods.len(), config_item.clone()));
+        }
+    }
+
+    let in_config = mod_config.iter().map(|e| &e.id).collect::<HashSet<_>>();
+    let mut absent = mods
+        .iter()
+        .filter(|e| !in_config.contains(&e.id))
+        .cloned()
+        .collect::<Vec<_>>();
+    absent.sort_by(|a, b| a.id.cmp(&b.id));
+    for mut nmod in absent {
+        if let ModKind::Normal(normal_mod) = &mut nmod.kind {
+            normal_mod.enabled = false;
+        }
+        new_mods.push(nmod);
+    }
+
+    Ok((new_mods, kept))
+}
+
+fn parse_config_item(element: &Element) -> anyhow::Result<ModConfigItem> {
+    let name = element.attributes.get("name").context("Missing name")?;
+    let enabled = element
+        .attributes
+        .get("enabled")
+        .context("Missing enabled")?
+        == "1";
+    Ok(ModConfigItem {
+        id: name.clone(),
+        enabled,
+        settings_fold_open: element
+            .attributes
+            .get("settings_fold_open")
+            .map(String::as_str)
+            == Some("1"),
+        workshop_item_id: element
+            .attributes
+            .get("workshop_item_id")
+            .cloned()
+            .unwrap_or("0".to_owned()),
+    })
+}
+
+/// text and comments are skipped, elements other than `<Mod>` are skipped with a warning
+fn parse_config<R: Read>(src: R, warnings: &mut Vec<String>) -> anyhow::Result<Vec<ModConfigItem>> {
+    let tree = Element::parse(src)?;
+    let mut items = Vec::new();
+    for node in tree.children.iter() {
+        let XMLNode::Element(element) = node else {
+            continue;
+        };
+        if element.name != "Mod" {
+            warnings.push(format!(
+                "Skipped a <{}> in the mod config, only <Mod> entries are used",
+                element.name
+            ));
+            continue;
+        }
+        items.push(parse_config_item(element)?);
+    }
+    Ok(items)
+}
+
+fn load_mod(path: &Path, is_workshop: bool) -> anyhow::Result<Option<Mod>> {
+    let mod_xml = path.join("mod.xml");
+    if !mod_xml.is_file() {
+        return Ok(None);
+    }
+
+    let file = File::open(mod_xml).context("Opening mod xml")?;
+    let reader = BufReader::new(file);
+    // TODO: port NXML to rust and use it here
+    let tree = Element::parse(reader).context("Parsing mod xml")?;
+    fn get(tree: &Element, key: String, default: String) -> String {
+        if let Some(x) = tree.attributes.get(&key) {
+            x.to_string()
+        } else {
+            default
+        }
+    }
+
+    let suffix = if let Some(x) = path.file_name() {
+        // a lossy id wouldn't match the config, so it would be dropped on save
+        x.to_str()
+            .context("Directory name isn't valid UTF-8")?
+            .to_owned()
+    } else {
+        bail!("Path doesn't have a filename???")
+    };
+    let mut id = suffix.clone();
+
+    let source = if is_workshop {
+        id = "".to_owned();
+        File::open(path.join("mod_id.txt"))
+            .context(format!("Opening mod_id.txt for {suffix}"))?
+            .read_to_string(&mut id)
+            .context(format!("Reading mod_id.txt for {suffix}"))?;
+        ModSource::Steam(SteamMod {
+            workshop_id: suffix.clone(),
+        })
+    } else if path.join(".git").is_dir() {
+        let repo = git2::Repository::discover(path).context("Finding git repo")?;
+        let remotes = repo.remotes().context("Getting git remotes")?;
+        let remote = repo
+            .find_remote("origin")
+            .ok()
+            .map(|x| x.url().map(|x| x.to_owned()))
+            .flatten()
+            .or(remotes.get(0).map(|x| x.to_owned()));
+        let host = if let Some(url) = &remote {
+            if url.contains("github") {
+                GitHost::Github
+            } else if url.contains("gitlab") {
+                GitHost::Gitlab
+            } else {
+                GitHost::Other
+            }
+        } else {
+            GitHost::Other
+        };
+        ModSource::Git(GitMod { remote, host })
+    } else {
+        ModSource::Manual
+    };
+
+    let workshop = match File::open(path.join("workshop.xml")) {
+        Ok(file) => WorkshopXml::parse(BufReader::new(file)).context("Parsing workshop.xml")?,
+        Err(_) => Default::default(),
+    };
+    let name = get(&tree, "name".to_owned(), "".to_owned());
+    let description = get(&tree, "description".to_owned(), "".to_owned());
+
+    let nmod = Mod {
+        source,
+        tags: workshop.tags,
+        author: workshop.author,
+        user: Default::default(),
+        load_error: None,
+        size: None,
+        modified: None,
+        vanilla_overrides: Vec::new(),
+        updated: false,
+        overridden_from: None,
+        other_copies: Vec::new(),
+        id,
+        kind: if get(&tree, "is_translation".to_owned(), "0".to_owned()) == "1" {
+            ModKind::Translation
+        } else if get(&tree, "is_game_mode".to_owned(), "0".to_owned()) == "1" {
+            ModKind::Gamemode
+        } else {
+            ModKind::Normal(NormalMod { enabled: false })
+        },
+        settings_fold_open: get(&tree, "settings_fold_open".to_string(), "0".to_owned()) == "1",
+        // the game shows what mod.xml says, so workshop.xml is only used when it says nothing
+        name: [name, workshop.name]
+            .into_iter()
+            .find(|e| !e.is_empty())
+            .unwrap_or(UNNAMED.to_owned()),
+        description: [description, workshop.description]
+            .into_iter()
+            .find(|e| !e.is_empty())
+            .unwrap_or_default()
+            .replace("\\n", "\n"),
+        unsafe_api: get(
+            &tree,
+            "request_no_api_restrictions".to_owned(),
+            "0".to_owned(),
+        ) == "1",
+    };
+    Ok(Some(nmod))
+}
+
+/// What a workshop.xml says about the mod, made by the game when uploading
+#[derive(Clone, Debug, Default)]
+struct WorkshopXml {
+    /// `None` if the mod doesn't support tags
+    tags: Option<Vec<String>>,
+    /// the published title
+    name: String,
+    description: String,
+    author: Option<String>,
+}
+
+impl WorkshopXml {
+    fn parse<R: Read>(reader: R) -> anyhow::Result<WorkshopXml> {
+        let xml = Element::parse(reader)?;
+        let get = |key: &str| {
+            xml.attributes
+                .get(key)
+                .map(|e| e.trim().to_owned())
+                .unwrap_or_default()
+        };
+        let tags = get("tags");
+        let author = get("author");
+        Ok(WorkshopXml {
+            // if it's default the mod doesn't support tags
+            tags: (!tags.is_empty())
+                .then(|| tags.split(',').map(|e| e.trim().to_owned()).collect()),
+            name: get("name"),
+            description: get("description"),
+            author: (!author.is_empty()).then_some(author),
+        })
+    }
+}
+
+/// workshop dirs without a mod.xml, usually a download that didn't finish, [`load_dir`] skips these
+pub fn incomplete_workshop_dirs(dir: &Path) -> anyhow::Result<Vec<PathBuf>> {
+    let mut dirs = Vec::new();
+    for item in fs::read_dir(dir).context(format!("Reading workshop dir {}", dir.display()))? {
+        let path = item
+            .context(format!("Accessing item in workshop dir {}", dir.display()))?
+            .path();
+        if path.is_dir() && !path.join("mod.xml").is_file() {
+            dirs.push(path);
+        }
+    }
+    dirs.sort();
+    Ok(dirs)
+}
+
+/// stands in for a mod that failed to load so it keeps its place in the config and can show why
+/// the id is a best guess, if it doesn't match the config the mod is dropped like any unknown mod
+fn placeholder_mod(path: &Path, is_workshop: bool, error: String) -> Mod {
+    let suffix = path
+        .file_name()
+        .map(|x| x.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let (id, source) = if is_workshop {
+        (
+            fs::read_to_string(path.join("mod_id.txt")).unwrap_or(suffix.clone()),
+            ModSource::Steam(SteamMod {
+                workshop_id: suffix.clone(),
+            }),
+        )
+    } else {
+        (suffix.clone(), ModSource::Manual)
+    };
+    Mod {
+        source,
+        kind: ModKind::Normal(NormalMod { enabled: false }),
+        name: suffix,
+        id,
+        description: String::new(),
+        unsafe_api: false,
+        settings_fold_open: false,
+        tags: None,
+        user: Default::default(),
+        load_error: Some(error),
+        size: None,
+        modified: None,
+        vanilla_overrides: Vec::new(),
+        updated: false,
+        author: None,
+        overridden_from: None,
+        other_copies: Vec::new(),
+    }
+}
+
+/// core game files that break other mods when replaced instead of appended to
+/// mod.xml has no way to say a mod does this, so the mod dir is checked for these paths
+const VANILLA_FILES: [&str; 12] = [
+    "data/biome/_biomes_all.xml",
+    "data/biome/_pixel_scenes.xml",
+    "data/entities/player.xml",
+    "data/magic_numbers.xml",
+    "data/materials.xml",
+    "data/scripts/gun/gun.lua",
+    "data/scripts/gun/gun_actions.lua",
+    "data/scripts/perks/perk.lua",
+    "data/scripts/perks/perk_list.lua",
+    "data/scripts/status_effects/status_list.lua",
+    "data/translations/common.csv",
+    "data/scripts/director_helpers.lua",
+];
+
+/// which of [`VANILLA_FILES`] the mod at `dir` has its own copy of
+fn vanilla_overrides(dir: &Path) -> Vec<String> {
+    VANILLA_FILES
+        .iter()
+        .filter(|e| dir.join(e).is_file())
+        .map(|e| e.to_string())
+        .collect()
+}
+
+/// returns each mod with the directory it was loaded from
+/// `on_loaded` is called for every directory item, so progress can be reported against [`count_dir`]
+/// directories without a mod.xml or with a name that isn't UTF-8 are skipped and added to `warnings`, mods that fail to load are replaced by a [`placeholder_mod`]
+fn load_dir(
+    dir: &Path,
+    is_workshop: bool,
+    on_loaded: &mut impl FnMut(),
+    warnings: &mut Vec<String>,
+) -> anyhow::Result<Vec<(Mod, PathBuf)>> {
+    let mut mods = Vec::new();
+    fs::read_dir(dir)
+        .context("Reading mods directory")?
+        .try_for_each::<_, anyhow::Result<()>>(|item| {
+            let item = item.context("Getting directory item")?;
+            let path = item.path();
+            if !path.is_dir() {
+                return Ok(());
+            }
+            on_loaded();
+            if path.file_name().and_then(|e| e.to_str()).is_none() {
+                warnings.push(format!(
+                    "Skipped {}, its name isn't valid UTF-8 so it can't be saved to the mod config",
+                    path.display()
+                ));
+                return Ok(());
+            }
+            let nmod = load_mod(&path, is_workshop)
+                .context(format!("Loading mod with path {}", path.display()));
+            let mut nmod = match nmod {
+                Ok(Some(x)) => x,
+                Ok(None) => {
+                    warnings.push(format!("Skipped {}, it has no mod.xml", path.display()));
+                    return Ok(());
+                }
+                Err(e) => {
+                    let error = format_error(&e);
+                    warnings.push(error.clone());
+                    placeholder_mod(&path, is_workshop, error)
+                }
+            };
+            nmod.modified = fs::metadata(&path).and_then(|e| e.modified()).ok();
+            nmod.vanilla_overrides = vanilla_overrides(&path);
+            if let Some(problem) = nmod.id_problem() {
+                warnings.push(problem);
+            }
+            mods.push((nmod, path));
+            Ok(())
+        })?;
+    Ok(mods)
+}
+
+#[cfg(test)]
+mod test {
+    use std::{fs, sync::mpsc};
+
+    use quickcheck::{Arbitrary, Gen};
+
+    use super::{
+        incomplete_workshop_dirs, load, merge_duplicates, parse_config, sort_mods,
+        vanilla_overrides, LoadPaths, WorkshopXml,
+    };
+    use crate::{
+        app::{config_xml, user_data::UserData, ModConfigItem},
+        r#mod::{KindOverride, Mod, ModKind, ModSource, ModUserData, NormalMod, SteamMod},
+    };
+
+    #[test]
+    fn non_normal_mods_saved_disabled() {
+        let config = parse_config(
+            r#"<Mods>
+	<Mod enabled="1" name="translation" settings_fold_open="0" workshop_item_id="0" />
+	<Mod enabled="1" name="gamemode" settings_fold_open="0" workshop_item_id="0" />
+	<Mod enabled="1" name="normal" settings_fold_open="0" workshop_item_id="0" />
+</Mods>"#
+                .as_bytes(),
+            &mut Vec::new(),
+        )
+        .expect("Parsing must work");
+        let mods = [
+            Mod::test("translation", ModKind::Translation),
+            Mod::test("gamemode", ModKind::Gamemode),
+            Mod::test("normal", ModKind::Normal(NormalMod { enabled: false })),
+        ];
+        let (sorted, kept) = sort_mods(&mods, &config, |_| false).expect("Sorting must work");
+        let xml = config_xml(&sorted, &kept);
+        assert!(xml.contains(r#"enabled="0" name="translation""#));
+        assert!(xml.contains(r#"enabled="0" name="gamemode""#));
+        assert!(xml.contains(r#"enabled="1" name="normal""#));
+    }
+
+    #[test]
+    fn id_problems() {
+        let check = |id: &str| Mod::test(id, ModKind::Translation).id_problem();
+        assert_eq!(check("spell_lab-2"), None);
+        assert!(check("").is_some());
+        let problem = check("my mod (a copy)").expect("Spaces and brackets aren't allowed");
+        assert!(problem.contains("(space, (, ))"));
+        assert!(check("mod\n").is_some_and(|e| e.contains("'\\n'")));
+    }
+
+    #[test]
+    fn kind_override() {
+        let config = parse_config(
+            r#"<Mods>
+	<Mod enabled="1" name="mislabeled" settings_fold_open="0" workshop_item_id="0" />
+</Mods>"#
+                .as_bytes(),
+            &mut Vec::new(),
+        )
+        .expect("Parsing must work");
+        let mut mods = [Mod::test("mislabeled", ModKind::Gamemode)];
+        let mut user_data = UserData::default();
+        user_data.mods.insert(
+            "mislabeled".to_owned(),
+            ModUserData {
+                kind: Some(KindOverride::Normal),
+                ..Default::default()
+            },
+        );
+        user_data.attach(&mut mods);
+        let (mut sorted, kept) = sort_mods(&mods, &config, |_| false).expect("Sorting must work");
+        assert!(config_xml(&sorted, &kept).contains(r#"enabled="1" name="mislabeled""#));
+
+        sorted[0].user.kind = None;
+        sorted[0].apply_kind_override();
+        assert!(matches!(sorted[0].kind, ModKind::Gamemode));
+        assert!(sorted[0].overridden_from.is_none());
+        assert!(config_xml(&sorted, &kept).contains(r#"enabled="0" name="mislabeled""#));
+    }
+
+    /// only characters xml can hold at all, the rest couldn't be written however they are escaped
+    fn xml_string(g: &mut Gen) -> String {
+        String::arbitrary(g)
+            .chars()
+            .filter(|e| {
+                matches!(e, '\t' | '\n' | '\r')
+                    || (!e.is_control() && !matches!(e, '\u{FFFE}' | '\u{FFFF}'))
+            })
+            .collect()
+    }
+
+    impl Arbitrary for ModConfigItem {
+        fn arbitrary(g: &mut Gen) -> Self {
+            ModConfigItem {
+                id: xml_string(g),
+                enabled: bool::arbitrary(g),
+                settings_fold_open: bool::arbitrary(g),
+                workshop_item_id: xml_string(g),
+            }
+        }
+    }
+
+    #[quickcheck]
+    fn config_round_trip(items: Vec<ModConfigItem>) -> bool {
+        // entries of unloaded mods are written as they are, so they go through the same builder as loaded ones
+        let kept = items.iter().map(|e| (0, e.clone())).collect();
+        let xml = config_xml(&[], &kept);
+        let parsed = parse_config(xml.as_bytes(), &mut Vec::new()).expect("Parsing must work");
+        parsed.len() == items.len()
+            && parsed.iter().zip(&items).all(|(a, b)| {
+                a.id == b.id
+                    && a.enabled == b.enabled
+                    && a.settings_fold_open == b.settings_fold_open
+                    && a.workshop_item_id == b.workshop_item_id
+            })
+    }
+
+    fn config_line(id: &str, enabled: bool) -> String {
+        format!(
+            r#"<Mod enabled="{}" name="{id}" settings_fold_open="0" workshop_item_id="0" />"#,
+            enabled as usize
+        )
+    }
+
+    fn sorted_ids(mods: &[Mod]) -> Vec<&str> {
+        mods.iter().map(|e| e.id.as_str()).collect()
+    }
+
+    #[test]
+    fn sort_missing_and_absent() {
+        let config = parse_config(
+            format!(
+                "<Mods>{}{}{}</Mods>",
+                config_line("b", true),
+                config_line("gone", true),
+                config_line("a", false)
+            )
+            .as_bytes(),
+            &mut Vec::new(),
+        )
+        .expect("Parsing must work");
+        let normal = ModKind::Normal(NormalMod { enabled: false });
+        let mods = [
+            Mod::test("z_absent", ModKind::Normal(NormalMod { enabled: true })),
+            Mod::test("a", normal),
+            Mod::test("b", normal),
+            Mod::test("absent", normal),
+        ];
+
+        let (sorted, kept) = sort_mods(&mods, &config, |_| false).expect("Sorting must work");
+        assert_eq!(sorted_ids(&sorted), ["b", "a", "absent", "z_absent"]);
+        assert!(matches!(
+            sorted[3].kind,
+            ModKind::Normal(NormalMod { enabled: false })
+        ));
+        assert!(kept.is_empty());
+
+        let (_, kept) = sort_mods(&mods, &config, |_| true).expect("Sorting must work");
+        assert_eq!(kept.len(), 1);
+        assert_eq!(kept[0].0, 1);
+        assert_eq!(kept[0].1.id, "gone");
+    }
+
+    #[test]
+    fn merged_duplicates() {
+        let normal = ModKind::Normal(NormalMod { enabled: false });
+        let mut steam = Mod::test("a", normal);
+        steam.source = ModSource::Steam(SteamMod {
+            workshop_id: "123".to_owned(),
+        });
+        steam.name = "Steam copy".to_owned();
+        steam.unsafe_api = true;
+        let mut mods =
+            merge_duplicates(vec![Mod::test("a", normal), Mod::test("b", normal), steam]);
+        assert_eq!(mods.len(), 2);
+        assert_eq!(mods[0].other_copies.len(), 1);
+        assert_eq!(mods[0].source.workshop_item_id(), "0");
+        // each copy is measured on its own
+        mods[0].set_size("123", 20);
+        mods[0].set_size("0", 10);
+        assert_eq!(mods[0].size, Some(10));
+        assert_eq!(mods[0].other_copies[0].size, Some(20));
+
+        let config = |workshop_item_id: &str| {
+            parse_config(
+                format!(
+                    "<Mods><Mod enabled=\"1\" name=\"a\" settings_fold_open=\"0\" workshop_item_id=\"{workshop_item_id}\" /></Mods>"
+                )
+                .as_bytes(),
+                &mut Vec::new(),
+            )
+            .expect("Parsing must work")
+        };
+        let (sorted, _) = sort_mods(&mods, &config("123"), |_| false).expect("Sorting must work");
+        assert_eq!(sorted[0].source.workshop_item_id(), "123");
+        // everything from the directory comes along with the source
+        assert_eq!(sorted[0].name, "Steam copy");
+        assert!(sorted[0].unsafe_api);
+        assert_eq!(sorted[0].size, Some(20));
+        assert_eq!(sorted[0].other_copies[0].source.workshop_item_id(), "0");
+        assert_eq!(sorted[0].other_copies[0].name, "a");
+        let (sorted, _) = sort_mods(&mods, &config("0"), |_| false).expect("Sorting must work");
+        assert_eq!(sorted[0].source.workshop_item_id(), "0");
+    }
+
+    #[test]
+    fn config_lists_both_copies() {
+        let normal = ModKind::Normal(NormalMod { enabled: false });
+        let mut steam = Mod::test("a", normal);
+        steam.source = ModSource::Steam(SteamMod {
+            workshop_id: "123".to_owned(),
+        });
+        let mods = merge_duplicates(vec![Mod::test("a", normal), Mod::test("b", normal), steam]);
+        let config = parse_config(
+            r#"<Mods>
+	<Mod enabled="1" name="a" settings_fold_open="0" workshop_item_id="123" />
+	<Mod enabled="1" name="b" settings_fold_open="0" workshop_item_id="0" />
+	<Mod enabled="0" name="a" settings_fold_open="0" workshop_item_id="0" />
+</Mods>"#
+                .as_bytes(),
+            &mut Vec::new(),
+        )
+        .expect("Parsing must work");
+        let (sorted, kept) = sort_mods(&mods, &config, |_| true).expect("Sorting must work");
+        assert_eq!(sorted_ids(&sorted), ["a", "b"]);
+        assert!(kept.is_empty());
+        assert_eq!(sorted[0].source.workshop_item_id(), "123");
+        assert!(matches!(
+            sorted[0].kind,
+            ModKind::Normal(NormalMod { enabled: true })
+        ));
+        let xml = config_xml(&sorted, &kept);
+        assert_eq!(xml.matches("name=\"a\"").count(), 1);
+    }
+
+    #[test]
+    fn sort_duplicate_ids() {
+        let normal = ModKind::Normal(NormalMod { enabled: false });
+        let mods = [Mod::test("a", normal), Mod::test("a", ModKind::Translation)];
+        assert!(sort_mods(&mods, &Vec::new(), |_| false).is_err());
+    }
+
+    #[test]
+    fn sort_enabled_only_normal() {
+        let config = parse_config(
+            format!(
+                "<Mods>{}{}{}</Mods>",
+                config_line("normal", true),
+                config_line("translation", true),
+                config_line("off", false)
+            )
+            .as_bytes(),
+            &mut Vec::new(),
+        )
+        .expect("Parsing must work");
+        let mods = [
+            Mod::test("normal", ModKind::Normal(NormalMod { enabled: false })),
+            Mod::test("translation", ModKind::Translation),
+            Mod::test("off", ModKind::Normal(NormalMod { enabled: true })),
+        ];
+        let (sorted, _) = sort_mods(&mods, &config, |_| false).expect("Sorting must work");
+        assert!(matches!(
+            sorted[0].kind,
+            ModKind::Normal(NormalMod { enabled: true })
+        ));
+        assert!(matches!(sorted[1].kind, ModKind::Translation));
+        assert!(matches!(
+            sorted[2].kind,
+            ModKind::Normal(NormalMod { enabled: false })
+        ));
+    }
+
+    #[test]
+    fn workshop_xml() {
+        let workshop = WorkshopXml::parse(
+            r#"<Mod name="Published" description="From the workshop" tags="Content, Quality of Life" author="someone" />"#
+                .as_bytes(),
+        )
+        .expect("Parsing must work");
+        assert_eq!(workshop.name, "Published");
+        assert_eq!(workshop.description, "From the workshop");
+        assert_eq!(workshop.author.as_deref(), Some("someone"));
+        assert_eq!(
+            workshop.tags,
+            Some(vec!["Content".to_owned(), "Quality of Life".to_owned()])
+        );
+
+        let bare = WorkshopXml::parse(r#"<Mod tags="" />"#.as_bytes()).expect("Parsing must work");
+        assert!(bare.tags.is_none() && bare.author.is_none() && bare.name.is_empty());
+    }
+
+    #[test]
+    fn config_variations() {
+        let mut warnings = Vec::new();
+        let config = parse_config(
+            format!(
+                "<Mods>\n\t<!-- written by hand -->\n\t{}\n\t<Other thing=\"1\" />\n\t{}\n</Mods>",
+                config_line("a", true),
+                config_line("b", false)
+            )
+            .as_bytes(),
+            &mut warnings,
+        )
+        .expect("Parsing must work");
+        assert_eq!(
+            config.iter().map(|e| e.id.as_str()).collect::<Vec<_>>(),
+            ["a", "b"]
+        );
+        assert_eq!(warnings.len(), 1);
+        assert!(warnings[0].contains("<Other>"));
+    }
+
+    #[test]
+    fn incomplete_dirs() {
+        let dir =
+            std::env::temp_dir().join(format!("noita_modman_workshop_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        for (name, files) in [
+            ("1", &["mod.xml", "mod_id.txt"][..]),
+            ("2", &["mod_id.txt"][..]),
+            ("3", &[][..]),
+        ] {
+            fs::create_dir_all(dir.join(name)).expect("Creating test dir must work");
+            for file in files {
+                fs::write(dir.join(name).join(file), "").expect("Writing test file must work");
+            }
+        }
+        let found = incomplete_workshop_dirs(&dir).expect("Scanning must work");
+        assert_eq!(found, [dir.join("2"), dir.join("3")]);
+        fs::remove_dir_all(&dir).expect("Cleaning up must work");
+    }
+
+    #[test]
+    fn overrides() {
+        let dir =
+            std::env::temp_dir().join(format!("noita_modman_overrides_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("data/scripts/gun")).expect("Creating test dir must work");
+        fs::create_dir_all(dir.join("files")).expect("Creating test dir must work");
+        for file in ["data/scripts/gun/gun_actions.lua", "files/gun_actions.lua"] {
+            fs::write(dir.join(file), "").expect("Writing test file must work");
+        }
+        assert_eq!(
+            vanilla_overrides(&dir),
+            ["data/scripts/gun/gun_actions.lua"]
+        );
+        fs::remove_dir_all(&dir).expect("Cleaning up must work");
+    }
+
+    #[test]
+    fn broken_user_data() {
+        let dir =
+            std::env::temp_dir().join(format!("noita_modman_user_data_{}", std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("mods/a")).expect("Creating test dir must work");
+        fs::write(dir.join("mods/a/mod.xml"), r#"<Mod name="A" />"#)
+            .expect("Writing test file must work");
+        fs::write(
+            dir.join("mod_config.xml"),
+            format!("<Mods>\n{}</Mods>", config_line("a", true)),
+        )
+        .expect("Writing test file must work");
+        fs::write(dir.join("mod_user_data.toml"), "[mods.a\ntags = [")
+            .expect("Writing test file must work");
+        let paths = LoadPaths {
+            mod_config: dir.join("mod_config.xml"),
+            mods_dir: Some(dir.join("mods")),
+            workshop_dir: None,
+            mod_settings_file: dir.join("mod_settings.bin"),
+            user_data_file: dir.join("mod_user_data.toml"),
+        };
+        let (sender, _receiver) = mpsc::channel();
+        let (_size_sender, sizes) = mpsc::channel();
+        let (data, _) = load(&paths, &sender, sizes).expect("Loading must still work");
+        assert!(data.user_data_failed);
+        assert!(data.user_data.mods.is_empty());
+        assert_eq!(sorted_ids(&data.mods), ["a"]);
+        assert!(data
+            .warnings
+            .iter()
+            .any(|e| e.starts_with("Mod user data couldn't be loaded")));
+        fs::remove_dir_all(&dir).expect("Cleaning up must work");
+    }
+}
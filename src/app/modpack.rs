@@ -3,12 +3,14 @@ use std::{
     cmp::max,
     collections::{HashMap, HashSet},
     io::{Read, Write},
-    iter::zip,
+    path::Path,
 };
 
 use anyhow::{anyhow, bail, Context, Error};
 use egui::{pos2, Id, InnerResponse, Rect, RichText, Sense, Ui};
 use fastlz;
+use flate2::{read::GzDecoder, write::GzEncoder};
+use serde::{Deserialize, Serialize};
 
 use crate::{
     app::{ModListConfig, UiSizedExt},
@@ -18,10 +20,10 @@ use crate::{
         Endianness::{Big, Little},
     },
     icons::{UNSAFE, YELLOW},
-    r#mod::ModKind,
+    r#mod::{Mod, ModKind},
 };
 
-use super::SCALE;
+use super::{name_order, SCALE};
 pub mod modsettings;
 
 #[derive(Clone, Debug, PartialEq)]
@@ -102,14 +104,60 @@ impl ModSettingsGroup {
         })
     }
 
-    pub fn render(&mut self, ui: &mut Ui) {
+    /// whether any key in this group contains `filter`, which should be lowercase
+    fn matches_filter(&self, path: &str, filter: &str) -> bool {
+        self.0.iter().any(|(key, setting)| {
+            let child_path = child_path(path, key);
+            child_path.to_lowercase().contains(filter)
+                || matches!(setting, ModSettingsNode::Group(group) if group.matches_filter(&child_path, filter))
+        })
+    }
+
+    /// sets the include of everything [`Self::render`] would show with this `filter`
+    pub fn include_matching(&mut self, path: &str, filter: &str, include: bool) {
         for (key, setting) in self.0.iter_mut() {
+            let child_path = child_path(path, key);
+            let matched = child_path.to_lowercase().contains(filter);
             match setting {
+                ModSettingsNode::Group(mod_settings_group) if matched => {
+                    mod_settings_group.include_all(include)
+                }
                 ModSettingsNode::Group(mod_settings_group) => {
+                    mod_settings_group.include_matching(&child_path, filter, include)
+                }
+                ModSettingsNode::Setting(togglable_setting) if matched => {
+                    togglable_setting.include = include
+                }
+                ModSettingsNode::Setting(_) => {}
+            }
+        }
+    }
+
+    /// `path` is the dotted key of this group, edits to next values are pushed to `edits` by their full key
+    /// only keys containing `filter` and the groups above them are shown, everything in a matching group is shown
+    pub fn render(
+        &mut self,
+        ui: &mut Ui,
+        path: &str,
+        filter: &str,
+        edits: &mut Vec<(String, ModSettingValue)>,
+    ) {
+        for (key, setting) in self.0.iter_mut() {
+            let child_path = child_path(path, key);
+            let child_filter = if child_path.to_lowercase().contains(filter) {
+                ""
+            } else {
+                filter
+            };
+            match setting {
+                ModSettingsNode::Group(mod_settings_group)
+                    if child_filter.is_empty()
+                        || mod_settings_group.matches_filter(&child_path, child_filter) =>
+                {
                     ui.push_id(Id::new(key as &str), |ui| {
                         let captured_key = key.clone();
                         let captured_checked = mod_settings_group.all_included();
-                        let check_include = CollapsingUi::new(
+                        let mut collapsing = CollapsingUi::new(
                             Id::new("Top"),
                             Box::new(move |ui| {
                                 ui.scope(|ui| {
@@ -135,9 +183,16 @@ impl ModSettingsGroup {
                                     )
                                 })
                             }),
-                        )
-                        .show(ui, |ui| mod_settings_group.render(ui))
-                        .inner;
+                        );
+                        // matches are usually deep in the tree, so filtering opens the groups on the way
+                        if !child_filter.is_empty() {
+                            collapsing = collapsing.open(Some(true));
+                        }
+                        let check_include = collapsing
+                            .show(ui, |ui| {
+                                mod_settings_group.render(ui, &child_path, child_filter, edits)
+                            })
+                            .inner;
 
                         match check_include {
                             Some(check) => mod_settings_group.include_all(check),
@@ -145,7 +200,8 @@ impl ModSettingsGroup {
                         }
                     });
                 }
-                ModSettingsNode::Setting(togglable_setting) => {
+                ModSettingsNode::Group(_) => {}
+                ModSettingsNode::Setting(togglable_setting) if child_filter.is_empty() => {
                     let mut include = togglable_setting.include;
                     ui.horizontal(|ui| {
                         let button_padding = ui.spacing().button_padding;
@@ -160,12 +216,24 @@ impl ModSettingsGroup {
 
                         ui.allocate_rect(icon_rect, Sense::click()); // TODO: remove the sense if possible, also perhaps pull the creating out into its own function
 
-                        ui.checkbox(&mut include, key as &str).on_hover_ui(|ui| {
-                            togglable_setting.pair.render(ui);
-                        });
+                        ui.checkbox(&mut include, key as &str)
+                            .on_hover_ui(|ui| {
+                                togglable_setting.pair.render(ui);
+                                ui.weak("Right click to edit the next value");
+                            })
+                            .context_menu(|ui| {
+                                ui.label("Next value");
+                                let mut next = togglable_setting.pair.next.clone();
+                                if next.edit(ui)
+                                    && togglable_setting.pair.set_next(next.clone()).is_ok()
+                                {
+                                    edits.push((child_path, next));
+                                }
+                            });
                     });
                     togglable_setting.include = include;
                 }
+                ModSettingsNode::Setting(_) => {}
             }
         }
     }
@@ -223,6 +291,22 @@ impl ModSettingsGroup {
         }
     }
 
+    /// like [`Self::include_all`] but only touches settings whose current value has the type `type_int`
+    pub fn include_type(&mut self, type_int: u32, include: bool) {
+        for (_, setting) in self.0.iter_mut() {
+            match setting {
+                ModSettingsNode::Group(mod_settings_group) => {
+                    mod_settings_group.include_type(type_int, include)
+                }
+                ModSettingsNode::Setting(togglable_setting) => {
+                    if togglable_setting.pair.current.type_int() == type_int {
+                        togglable_setting.include = include
+                    }
+                }
+            }
+        }
+    }
+
     pub fn include_all(&mut self, include: bool) {
         for (_, setting) in self.0.iter_mut() {
             match setting {
@@ -235,19 +319,143 @@ impl ModSettingsGroup {
     }
 }
 
+fn child_path(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_owned()
+    } else {
+        path.to_owned() + "." + key
+    }
+}
+
+/// file names of the packs in the order the user arranged them, one per line
+/// it starts with a dot so [`super::loader`] doesn't try to load it as a pack
+pub const ORDER_FILE: &str = ".order";
+
+pub fn load_order(path: &Path) -> anyhow::Result<Vec<String>> {
+    name_order::load(path).context("Loading modpack order")
+}
+
+pub fn save_order(path: &Path, packs: &[ModPack]) -> anyhow::Result<()> {
+    name_order::save(path, packs.iter().map(|e| e.file_name.as_str()))
+        .context("Saving modpack order")
+}
+
+/// packs in `order` come first in that order, the rest keep their relative order after them
+pub fn sort_by_order(packs: &mut [ModPack], order: &[String]) {
+    name_order::sort(packs, order, |e| e.file_name.as_str());
+}
+
+/// settings are keyed by `mod_id.setting`, so the mod is everything before the first dot
+pub fn setting_mod_id(key: &str) -> &str {
+    key.split('.').next().unwrap_or(key)
+}
+
+/// setting types that can be bulk included, by their [`ModSettingValue::type_int`]
+const FILTERABLE_TYPES: [(u32, &str); 3] = [(1, "bool"), (2, "number"), (3, "string")];
+
+/// What happened to a modpack's row this frame
+#[derive(Clone, Debug, Default)]
+pub struct PackRowResponse {
+    /// the caller should apply the pack, it owns the apply options
+    pub apply_clicked: bool,
+}
+
+/// Compression for the whole pack file, separate from the fastlz used by mod settings
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum PackCompression {
+    #[default]
+    None,
+    Gzip,
+}
+
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_MAGIC: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+/// What applying a pack does to the settings already in the mod list
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum SettingsApplyMode {
+    /// the pack's values replace the existing ones
+    #[default]
+    Overwrite,
+    /// only settings that don't exist yet are added
+    Merge,
+    /// the settings are left alone
+    ModsOnly,
+}
+
+impl SettingsApplyMode {
+    pub const ALL: [SettingsApplyMode; 3] = [
+        SettingsApplyMode::Overwrite,
+        SettingsApplyMode::Merge,
+        SettingsApplyMode::ModsOnly,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            SettingsApplyMode::Overwrite => "Overwrite",
+            SettingsApplyMode::Merge => "Merge",
+            SettingsApplyMode::ModsOnly => "Mods only",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            SettingsApplyMode::Overwrite => "Replace your settings with the pack's",
+            SettingsApplyMode::Merge => "Only add settings you don't have yet",
+            SettingsApplyMode::ModsOnly => "Leave your settings alone",
+        }
+    }
+}
+
+/// What applying a pack does to the mods that aren't in it
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum ModApplyMode {
+    /// the pack becomes the whole loadout, every other mod is disabled
+    #[default]
+    Replace,
+    /// the pack's mods are enabled on top of the current loadout, nothing is disabled
+    Add,
+}
+
+impl ModApplyMode {
+    pub const ALL: [ModApplyMode; 2] = [ModApplyMode::Replace, ModApplyMode::Add];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            ModApplyMode::Replace => "Replace",
+            ModApplyMode::Add => "Add",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            ModApplyMode::Replace => "Enable only the pack's mods and disable the rest",
+            ModApplyMode::Add => "Enable the pack's mods and leave your other enabled mods on",
+        }
+    }
+}
+
+/// the schema `ModPack::save` writes
+/// v1 added a byte after the mods saying whether the pack has settings, v0 packs always do
+pub const CURRENT_VERSION: usize = 1;
+
 #[derive(Clone, Debug)]
 pub struct ModPack {
     file_name: String,
     name: String,
     mods: Vec<String>,
     settings: ModSettings,
+    /// exported without settings, applying it never touches the live settings whatever the apply mode
+    mods_only: bool,
+    /// schema the pack was loaded from, older packs are missing fields until re-exported
+    version: usize,
 }
 
 fn decompress_file<R: Read>(mut reader: R, file_size: usize) -> anyhow::Result<Vec<u8>> {
     let compressed_size = reader.read_le::<u32>().context("Reading compressed size")?;
     if compressed_size as usize + 8 != file_size {
         bail!(
-            "File should be {} when compressed according to content, but is actually {file_size}",
+            "File should be {} bytes according to its header, but is actually {file_size}. It may be truncated or from an incompatible Noita version",
             compressed_size + 8
         );
     }
@@ -261,50 +469,158 @@ fn decompress_file<R: Read>(mut reader: R, file_size: usize) -> anyhow::Result<V
         .context("Reading the compressed data to a vec")?;
     if compressed_size != decompressed_size {
         let mut output = vec![0; decompressed_size as usize];
-        fastlz::decompress(&compressed, &mut output)
-            .map_err(|_| anyhow!("FastLZ failed to decompress"))?;
+        // fastlz reports failure by decompressing nothing
+        let decompressed_len = fastlz::decompress(&compressed, &mut output)
+            .map_err(|_| anyhow!("FastLZ failed to decompress"))?
+            .len();
+        if decompressed_len != decompressed_size as usize {
+            bail!("FastLZ decompressed {decompressed_len} bytes, expected {decompressed_size}");
+        }
         Ok(output)
     } else {
         Ok(compressed)
     }
 }
 
-fn compress_file<W: Write>(mut writer: W, buf: &[u8]) -> anyhow::Result<()> {
-    let mut our_buf = buf;
-    let mut new_buf = [0; 16];
-    if buf.len() < 16 {
-        new_buf[..buf.len()].copy_from_slice(buf);
-        our_buf = &new_buf;
-    }
-    let mut output = vec![0; max(buf.len() * 2, 128)]; // apparently 5% and 66 bytes is safe, but i have 0 trust of that
-    let output_slice =
-        fastlz::compress(our_buf, &mut output).map_err(|_| anyhow!("FastLZ failed to compress"))?;
-    if output_slice.len() >= buf.len() {
-        writer
-            .write_le::<u32>(buf.len() as u32)
-            .context("Writing output length")?;
-        writer
-            .write_le::<u32>(buf.len() as u32)
-            .context("Writing input length")?;
-        writer.write_all(buf).context("Writing compressed buffer")?;
+const FASTLZ_MIN_INPUT: usize = 16;
+
+/// FastLZ needs the output to be 5% larger than the input and never smaller than 66 bytes, incompressible data
+/// becomes literal runs of at most 32 bytes with a 1 byte header each so this bound always holds
+fn max_compressed_size(input_len: usize) -> usize {
+    const MIN_OUTPUT: usize = 66;
+    max(input_len + input_len.div_ceil(20), MIN_OUTPUT)
+}
+
+/// How a FastLZ file is stored, sizes don't include the 8 byte header
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct CompressionStats {
+    pub compressed: usize,
+    pub decompressed: usize,
+}
+
+impl CompressionStats {
+    /// FastLZ didn't make the data smaller so it was written as is
+    pub fn stored(&self) -> bool {
+        self.compressed == self.decompressed
+    }
+
+    pub fn describe(&self) -> String {
+        let compressed = Mod::format_size(self.compressed as u64);
+        if self.stored() {
+            return format!("{compressed}, stored uncompressed as FastLZ wouldn't make it smaller");
+        }
+        format!(
+            "{compressed} compressed from {} ({:.0}%)",
+            Mod::format_size(self.decompressed as u64),
+            self.compressed as f64 / self.decompressed as f64 * 100.0
+        )
+    }
+}
+
+fn compress_file<W: Write>(mut writer: W, buf: &[u8]) -> anyhow::Result<CompressionStats> {
+    let mut output = vec![0; max_compressed_size(buf.len())];
+    // fastlz can't compress less than 16 bytes, padding it would make the padding part of the decompressed data
+    // so tiny buffers are just stored
+    let compressed = if buf.len() < FASTLZ_MIN_INPUT {
+        None
     } else {
-        writer
-            .write_le::<u32>(output_slice.len() as u32)
-            .context("Writing output length")?;
-        writer
-            .write_le::<u32>(buf.len() as u32)
-            .context("Writing input length")?;
-        writer
-            .write_all(output_slice)
-            .context("Writing compressed buffer")?;
-    }
-    Ok(())
+        Some(fastlz::compress(buf, &mut output).map_err(|_| anyhow!("FastLZ failed to compress"))?)
+    };
+    let compressed_len = match compressed.filter(|e| e.len() < buf.len()) {
+        Some(output_slice) => {
+            writer
+                .write_le::<u32>(output_slice.len() as u32)
+                .context("Writing output length")?;
+            writer
+                .write_le::<u32>(buf.len() as u32)
+                .context("Writing input length")?;
+            writer
+                .write_all(output_slice)
+                .context("Writing compressed buffer")?;
+            output_slice.len()
+        }
+        None => {
+            writer
+                .write_le::<u32>(buf.len() as u32)
+                .context("Writing output length")?;
+            writer
+                .write_le::<u32>(buf.len() as u32)
+                .context("Writing input length")?;
+            writer.write_all(buf).context("Writing compressed buffer")?;
+            buf.len()
+        }
+    };
+    Ok(CompressionStats {
+        compressed: compressed_len,
+        decompressed: buf.len(),
+    })
+}
+
+/// What [`ModPack::apply`] changed, so it can be shown without comparing the list before and after
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ApplyResult {
+    /// ids in list order
+    pub enabled: Vec<String>,
+    pub disabled: Vec<String>,
+    /// ids in the pack that aren't in the mod list
+    pub missing: Vec<String>,
+    /// setting keys that weren't set before
+    pub settings_added: Vec<String>,
+    /// setting keys whose value was replaced by the pack's
+    pub settings_overwritten: Vec<String>,
+    /// settings of mods that aren't installed, left out of the live settings
+    pub settings_skipped: usize,
+}
+
+impl ApplyResult {
+    /// one line like "Enabled 3 mods, disabled 1 and added 12 settings"
+    pub fn summary(&self) -> String {
+        let plural = |count: usize| if count == 1 { "" } else { "s" };
+        let mut parts = Vec::new();
+        if !self.enabled.is_empty() {
+            parts.push(format!(
+                "enabled {} mod{}",
+                self.enabled.len(),
+                plural(self.enabled.len())
+            ));
+        }
+        if !self.disabled.is_empty() {
+            parts.push(format!("disabled {}", self.disabled.len()));
+        }
+        let settings = self.settings_added.len();
+        if settings != 0 {
+            parts.push(format!("added {settings} setting{}", plural(settings)));
+        }
+        let settings = self.settings_overwritten.len();
+        if settings != 0 {
+            parts.push(format!("changed {settings} setting{}", plural(settings)));
+        }
+        let Some(last) = parts.pop() else {
+            return "Nothing changed".to_owned();
+        };
+        let summary = if parts.is_empty() {
+            last
+        } else {
+            parts.join(", ") + " and " + &last
+        };
+        // only the first letter, the rest of the parts are lowercase already
+        let mut chars = summary.chars();
+        chars
+            .next()
+            .map_or(String::new(), |e| e.to_uppercase().chain(chars).collect())
+    }
 }
 
 impl ModPack {
-    fn load_v0<R: Read>(mut reader: R, file_name: String) -> anyhow::Result<ModPack> {
+    /// v1 is v0 with the settings flag, so both are read here
+    fn load_v0_v1<R: Read>(
+        mut reader: R,
+        file_name: String,
+        version: usize,
+    ) -> anyhow::Result<ModPack> {
+        // the name is only displayed, so a bad byte in it shouldn't stop the pack loading
         let name = reader
-            .read_str::<usize>(Little)
+            .read_str_lossy::<usize>(Little)
             .context("Reading modpack name")?;
         let err_name = name.clone();
         (|| {
@@ -321,9 +637,18 @@ impl ModPack {
                 mods.push(mod_name);
             }
 
-            let num_settings = reader
-                .read_le::<usize>()
-                .context("Reading modpack number of settings")?;
+            let mods_only = version >= 1
+                && reader
+                    .read_le::<u8>()
+                    .context("Reading whether the modpack has settings")?
+                    == 0;
+            let num_settings = if mods_only {
+                0
+            } else {
+                reader
+                    .read_le::<usize>()
+                    .context("Reading modpack number of settings")?
+            };
 
             let mut settings = HashMap::new();
             for i in 0..num_settings {
@@ -340,63 +665,153 @@ impl ModPack {
                     values: settings,
                     ..Default::default()
                 },
+                mods_only,
+                version,
             })
         })()
         .context(format!("Loading pack {err_name}"))
     }
 
-    pub fn apply(&self, mod_list_config: &mut ModListConfig) {
+    /// settings for mods that aren't in `installed` are skipped so they don't end up in the live settings
+    /// with [`ModApplyMode::Add`] the pack's mods are still put in pack order, but only among their own slots
+    pub fn apply(
+        &self,
+        mod_list_config: &mut ModListConfig,
+        installed: &HashSet<String>,
+        mods_mode: ModApplyMode,
+        mode: SettingsApplyMode,
+    ) -> ApplyResult {
+        let mut result = ApplyResult::default();
         let mut enabled = HashMap::new();
         for (i, nmod) in self.mods.iter().enumerate() {
             enabled.insert(nmod, i);
         }
+        let listed = mod_list_config
+            .mods
+            .iter()
+            .map(|e| e.id.as_str())
+            .collect::<HashSet<_>>();
+        result.missing = self
+            .mods
+            .iter()
+            .filter(|e| !listed.contains(e.as_str()))
+            .cloned()
+            .collect();
 
-        // TODO: make this fast with swaps
-        let mut enabled_mods = Vec::new();
+        // the slots the pack's mods are in, and where each one is in the pack
         let mut enabled_idxs = Vec::new();
+        let mut pack_positions = Vec::new();
         for (i, nmod) in mod_list_config.mods.iter_mut().enumerate() {
             if let ModKind::Normal(normal_mod) = &mut nmod.kind {
+                let was_enabled = normal_mod.enabled;
                 if let Some(v) = enabled.get(&nmod.id) {
                     normal_mod.enabled = true;
-                    enabled_mods.push((nmod.clone(), *v));
                     enabled_idxs.push(i);
-                } else {
+                    pack_positions.push(*v);
+                } else if mods_mode == ModApplyMode::Replace {
                     normal_mod.enabled = false;
                 }
+                match (was_enabled, normal_mod.enabled) {
+                    (false, true) => result.enabled.push(nmod.id.clone()),
+                    (true, false) => result.disabled.push(nmod.id.clone()),
+                    _ => {}
+                }
             }
         }
 
-        enabled_mods.sort_by_key(|e| e.1);
-        for (nmod, idx) in zip(enabled_mods, enabled_idxs) {
-            mod_list_config.mods[idx] = nmod.0;
+        // the enabled mods are reordered among their own slots to match the pack, other mods don't move
+        let mut wanted = (0..enabled_idxs.len()).collect::<Vec<_>>();
+        wanted.sort_by_key(|e| pack_positions[*e]);
+        // which enabled mod is in each slot, and which slot each enabled mod is in
+        let mut in_slot = (0..enabled_idxs.len()).collect::<Vec<_>>();
+        let mut slot_of = in_slot.clone();
+        for (slot, want) in wanted.into_iter().enumerate() {
+            let from = slot_of[want];
+            if from == slot {
+                continue;
+            }
+            mod_list_config
+                .mods
+                .swap(enabled_idxs[slot], enabled_idxs[from]);
+            let displaced = in_slot[slot];
+            in_slot[from] = displaced;
+            slot_of[displaced] = from;
+            in_slot[slot] = want;
+            slot_of[want] = slot;
         }
 
+        if mode == SettingsApplyMode::ModsOnly || self.mods_only {
+            return result;
+        }
+        let mut applied = HashSet::new();
         for (key, values) in self.settings.values.iter() {
-            mod_list_config
-                .mod_settings
-                .values
-                .insert(key.clone(), values.clone());
+            if !installed.contains(setting_mod_id(key)) {
+                result.settings_skipped += 1;
+                continue;
+            }
+            let live = &mut mod_list_config.mod_settings.values;
+            if mode == SettingsApplyMode::Merge && live.contains_key(key) {
+                continue;
+            }
+            if live.insert(key.clone(), values.clone()).is_some() {
+                result.settings_overwritten.push(key.clone());
+            } else {
+                result.settings_added.push(key.clone());
+            }
+            applied.insert(key.clone());
         }
+        // the values are in a hash map, sorting keeps the result the same between runs
+        result.settings_added.sort();
+        result.settings_overwritten.sort();
 
-        mod_list_config
-            .mod_settings
-            .recompute_grouped(&self.settings.values.iter().map(|e| e.0.clone()).collect());
+        mod_list_config.mod_settings.recompute_grouped(&applied);
+        result
     }
 
+    /// handles both plain and gzipped packs, which one is detected from the first bytes
     pub fn load<R: Read>(mut reader: R, file_name: String) -> anyhow::Result<ModPack> {
+        let mut content = Vec::new();
+        reader
+            .read_to_end(&mut content)
+            .context("Reading modpack file")?;
+        if content.starts_with(&GZIP_MAGIC) {
+            Self::load_raw(GzDecoder::new(content.as_slice()), file_name)
+                .context("Loading gzipped modpack")
+        } else if content.starts_with(&ZSTD_MAGIC) {
+            bail!("Zstd compressed modpacks aren't supported, recompress it with gzip")
+        } else {
+            Self::load_raw(content.as_slice(), file_name)
+        }
+    }
+
+    fn load_raw<R: Read>(mut reader: R, file_name: String) -> anyhow::Result<ModPack> {
         let version = reader
             .read_le::<usize>()
             .context("Reading modpack schema version")?;
         match version {
-            0 => Self::load_v0(reader, file_name),
-            1.. => bail!("Attempted to load future modpack schema (v{version})"),
+            0 | 1 => Self::load_v0_v1(reader, file_name, version),
+            2.. => bail!("Attempted to load future modpack schema (v{version})"),
         }
     }
 
-    pub fn save<W: Write>(&self, mut writer: W) -> anyhow::Result<()> {
+    pub fn save<W: Write>(&self, writer: W, compression: PackCompression) -> anyhow::Result<()> {
+        match compression {
+            PackCompression::None => self.save_raw(writer),
+            PackCompression::Gzip => {
+                let mut encoder = GzEncoder::new(writer, flate2::Compression::default());
+                self.save_raw(&mut encoder)?;
+                encoder
+                    .finish()
+                    .context(format!("Compressing pack {}", self.name))?;
+                Ok(())
+            }
+        }
+    }
+
+    fn save_raw<W: Write>(&self, mut writer: W) -> anyhow::Result<()> {
         (|| {
             writer
-                .write_le::<usize>(0)
+                .write_le::<usize>(CURRENT_VERSION)
                 .context("Writing modpack schema version")?;
             writer
                 .write_str::<usize>(&self.name, Little)
@@ -414,6 +829,13 @@ impl ModPack {
                     .context("Writing mod name")?;
             }
 
+            writer
+                .write_le::<u8>(if self.mods_only { 0 } else { 1 })
+                .context("Writing whether the modpack has settings")?;
+            if self.mods_only {
+                return Ok(());
+            }
+
             let set = self.settings.grouped.to_set();
             let saved_settings = self
                 .settings
@@ -440,18 +862,69 @@ impl ModPack {
         .context(format!("Saving pack {}", self.name))
     }
 
-    /// Returns an optional error message which should be displayed, can't borrow `&mut App` because we need to iterate over modpacks when calling this
+    /// Problems with the pack's settings that loading doesn't catch, one message per bad setting
+    /// Keys are already valid utf8 as they are read into a `String`
+    pub fn validate(&self) -> Vec<String> {
+        let mut problems = self
+            .settings
+            .values
+            .iter()
+            .filter_map(|(key, pair)| {
+                if key.is_empty() {
+                    Some("A setting has an empty key".to_owned())
+                } else if !pair.is_consistent() {
+                    Some(format!(
+                        "Setting {key} has current type {} but next type {}",
+                        pair.current.type_int(),
+                        pair.next.type_int()
+                    ))
+                } else {
+                    None
+                }
+            })
+            .collect::<Vec<_>>();
+        // hashmap order isn't stable
+        problems.sort();
+        problems
+    }
+
+    /// Message listing the mods in this pack which aren't installed, if there are any
+    pub fn missing_mods(&self, installed: &HashSet<String>) -> Option<String> {
+        let missing = self
+            .mods
+            .iter()
+            .filter(|e| !installed.contains(*e))
+            .map(|e| e.as_str())
+            .collect::<Vec<_>>();
+        if missing.is_empty() {
+            None
+        } else {
+            Some("Missing mods:\n".to_owned() + &missing.join("\n"))
+        }
+    }
+
+    /// how many of the `enabled` mods applying this pack would turn off, none when adding
+    pub fn disabled_by_apply(&self, enabled: &HashSet<String>, mode: ModApplyMode) -> usize {
+        if mode == ModApplyMode::Add {
+            return 0;
+        }
+        let in_pack = self.mods.iter().collect::<HashSet<_>>();
+        enabled.iter().filter(|e| !in_pack.contains(e)).count()
+    }
+
+    /// Returns what the user did with the row, applying is left to the caller as it needs `&mut App` state
     /// If you are doing a sizing pass to get the row rect, shade_bg must be false
+    /// `mode` is how applying treats mods that aren't in the pack, for the hover
     // TODO: make shade_bg an Option<(bool, Rect)> type deal
     pub fn render(
         &self,
         ui: &mut Ui,
-        mod_list: &mut ModListConfig,
-        search_term: &mut String,
         installed: &HashSet<String>,
+        enabled: &HashSet<String>,
+        mode: ModApplyMode,
         shade_bg: bool,
         row_rect: Option<Rect>,
-    ) -> InnerResponse<Option<String>> {
+    ) -> InnerResponse<PackRowResponse> {
         ui.horizontal(|ui| {
             if shade_bg {
                 let painter = ui.painter();
@@ -461,29 +934,12 @@ impl ModPack {
                 painter.rect_filled(cursor, 0.0, ui.visuals().faint_bg_color);
             }
 
-            let mut error: Option<String> = None;
-            for nmod in self.mods.iter() {
-                if !installed.contains(nmod) {
-                    error = Some(
-                        error
-                            .clone() // TODO: this is not needed, find a way to fix
-                            .map_or_else(|| nmod.clone(), |e| e + "\n" + nmod),
-                    );
-                }
-            }
-            error = error.map(|e| "Missing mods:\n".to_owned() + &e);
+            let error = self.missing_mods(installed);
 
-            let result = if ui.button("Apply").clicked() {
-                *search_term = self.name.clone();
-                self.apply(mod_list);
-                if let Some(err) = &error {
-                    Some(err.clone())
-                } else {
-                    None
-                }
-            } else {
-                None
-            };
+            let mut result = PackRowResponse::default();
+            if ui.button("Apply").clicked() {
+                result.apply_clicked = true;
+            }
 
             ui.fixed_size_group(40.0 * SCALE, |ui| {
                 if let Some(err) = &error {
@@ -493,11 +949,38 @@ impl ModPack {
             });
 
             ui.label(&self.name).on_hover_ui(|ui| {
-                ui.label(format!("({})\n", &self.file_name));
+                ui.label(format!("({})", &self.file_name));
+                ui.label(RichText::new(format!("Schema v{}", self.version())).weak());
+                if self.mods_only() {
+                    ui.label("Mods only, applying it leaves your settings alone");
+                }
+                if self.is_outdated() {
+                    ui.label(
+                        RichText::new("Made by an older version, re-export it to update it")
+                            .color(YELLOW),
+                    );
+                }
+                let disabled = self.disabled_by_apply(enabled, mode);
+                if disabled > 0 {
+                    ui.label(
+                        RichText::new(format!(
+                            "Applying disables {disabled} currently enabled mod{}",
+                            if disabled == 1 { "" } else { "s" }
+                        ))
+                        .color(YELLOW),
+                    );
+                }
+                ui.label("");
                 for nmod in self.mods.iter() {
                     ui.label(nmod);
                 }
             });
+            let count = RichText::new(format!("({} mods)", self.mods.len()));
+            ui.label(if error.is_some() {
+                count.color(ui.visuals().error_fg_color)
+            } else {
+                count.weak()
+            });
 
             result
         })
@@ -514,13 +997,47 @@ impl ModPack {
             name,
             mods: mods.to_vec(),
             settings: settings.clone(),
+            mods_only: false,
+            version: CURRENT_VERSION,
         }
     }
 
+    /// a pack that only sets the mod list, distinct from one whose include tree was left empty
+    pub fn without_settings(mut self) -> ModPack {
+        self.settings = Default::default();
+        self.mods_only = true;
+        self
+    }
+
+    pub fn mods_only(&self) -> bool {
+        self.mods_only
+    }
+
+    pub fn version(&self) -> usize {
+        self.version
+    }
+
+    pub fn is_outdated(&self) -> bool {
+        self.version < CURRENT_VERSION
+    }
+
+    /// `query` should already be trimmed and lowercased
+    pub fn matches_search(&self, query: &str) -> bool {
+        self.name.to_lowercase().contains(query) || self.file_name.to_lowercase().contains(query)
+    }
+
     pub fn file_name(&self) -> &str {
         &self.file_name
     }
 
+    pub fn mods(&self) -> &[String] {
+        &self.mods
+    }
+
+    pub fn settings(&self) -> &ModSettings {
+        &self.settings
+    }
+
     pub fn name(&self) -> &str {
         &self.name
     }
@@ -581,6 +1098,11 @@ impl ModSettings {
         let mut settings = HashMap::new();
         let mut decompressed =
             ByteVec(decompress_file(reader, file_size).context("Decompressing file")?);
+        // the header was checked against the file size while decompressing
+        let compression = CompressionStats {
+            compressed: file_size - 8,
+            decompressed: decompressed.0.len(),
+        };
         let expected_num_entries = decompressed
             .read_be::<u64>()
             .context("Reading expected entries")?;
@@ -597,18 +1119,20 @@ impl ModSettings {
         }
         let settings = ModSettings {
             grouped: Self::compute_grouped(&settings),
+            mod_ids: Self::compute_mod_ids(&settings),
             values: settings,
+            compression: Some(compression),
         };
         Ok(settings)
     }
 
-    pub fn save<W: Write>(&self, writer: W) -> anyhow::Result<()> {
+    pub fn save<W: Write>(&self, writer: W) -> anyhow::Result<CompressionStats> {
         let mut buf = ByteVec(Vec::new());
-        buf.write_be::<u64>(self.values.len() as u64)
+        buf.write_be::<u64>(self.len() as u64)
             .context("Writing number of settings")?;
-        for (key, values) in self.values.iter() {
+        for (key, values) in self.iter() {
             let setting = ModSetting {
-                key: key.clone(),
+                key: key.to_owned(),
                 values: values.clone(),
             };
             setting.save(&mut buf)?; // TODO: remove clones
@@ -616,8 +1140,56 @@ impl ModSettings {
         compress_file(writer, &buf.0).context("Compressing to file")
     }
 
-    pub fn render(&mut self, ui: &mut Ui) {
-        self.grouped.render(ui);
+    /// edits made in the tree are copied back into `values` so they get saved
+    /// `filter` is the contents of the filter box, it narrows the tree down to matching keys
+    pub fn render(&mut self, ui: &mut Ui, filter: &mut String) -> anyhow::Result<()> {
+        if self.is_empty() {
+            ui.label("No mod settings");
+            return Ok(());
+        }
+        ui.horizontal(|ui| {
+            ui.label("Filter");
+            ui.text_edit_singleline(filter)
+                .on_hover_text("Only show settings whose key contains this");
+        });
+        let lower_filter = filter.trim().to_lowercase();
+        if !lower_filter.is_empty() {
+            ui.horizontal(|ui| {
+                for (label, include) in [("Include", true), ("Exclude", false)] {
+                    if ui
+                        .small_button(format!("{label} visible"))
+                        .on_hover_text(format!("{label} every setting the filter shows"))
+                        .clicked()
+                    {
+                        self.grouped.include_matching("", &lower_filter, include);
+                    }
+                }
+            });
+        }
+        for (label, include) in [("Include", true), ("Exclude", false)] {
+            ui.horizontal(|ui| {
+                ui.label(label);
+                for (type_int, type_name) in FILTERABLE_TYPES {
+                    if ui
+                        .small_button(type_name)
+                        .on_hover_text(format!("{label} all {type_name} settings"))
+                        .clicked()
+                    {
+                        self.grouped.include_type(type_int, include);
+                    }
+                }
+            });
+        }
+        let mut edits = Vec::new();
+        self.grouped.render(ui, "", &lower_filter, &mut edits);
+        for (key, next) in edits {
+            self.values
+                .get_mut(&key)
+                .context(format!("Finding edited setting {key}"))?
+                .set_next(next)
+                .context(format!("Editing setting {key}"))?;
+        }
+        Ok(())
     }
 
     pub fn compute_grouped(map: &HashMap<String, ModSettingPair>) -> ModSettingsGroup {
@@ -641,16 +1213,450 @@ impl ModSettings {
         tree
     }
 
-    pub fn recompute_grouped(&mut self, set: &HashSet<String>) {
+    /// settings that were included stay included, keyed by their dotted path, along with anything in `also_include`
+    pub fn recompute_grouped(&mut self, also_include: &HashSet<String>) {
+        let mut set = self.grouped.to_set();
+        set.extend(also_include.iter().cloned());
         self.grouped = Self::compute_grouped(&self.values);
-        self.grouped.apply_set(set, "".to_owned());
+        self.grouped.apply_set(&set, "".to_owned());
+        self.mod_ids = Self::compute_mod_ids(&self.values);
+    }
+
+    pub fn compute_mod_ids(map: &HashMap<String, ModSettingPair>) -> HashSet<String> {
+        map.keys().map(|e| setting_mod_id(e).to_owned()).collect()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use super::{compress_file, decompress_file};
-    use crate::ext::ByteVec;
+    use std::collections::{HashMap, HashSet};
+
+    use super::{
+        compress_file, decompress_file,
+        modsettings::{ModSettingPair, ModSettingValue},
+        ModApplyMode, ModPack, ModSettings, PackCompression, SettingsApplyMode, CURRENT_VERSION,
+    };
+    use crate::{
+        app::ModListConfig,
+        ext::{ByteVec, ByteWriterExt, Endianness::Little},
+        r#mod::{Mod, ModKind, NormalMod},
+    };
+
+    fn normal_mod(id: &str, enabled: bool) -> Mod {
+        Mod::test(id, ModKind::Normal(NormalMod { enabled }))
+    }
+
+    #[test]
+    fn save_load_compressed() {
+        let mut values = HashMap::new();
+        values.insert(
+            "a.setting".to_owned(),
+            ModSettingPair {
+                current: ModSettingValue::String("value".to_owned()),
+                next: ModSettingValue::String("value".to_owned()),
+            },
+        );
+        let mut settings = ModSettings {
+            grouped: ModSettings::compute_grouped(&values),
+            values,
+            ..Default::default()
+        };
+        settings.grouped.include_all(true);
+        let pack = ModPack::new(
+            "pack".to_owned(),
+            "pack".to_owned(),
+            &["a".to_owned(), "b".to_owned()],
+            &settings,
+        );
+        for compression in [PackCompression::None, PackCompression::Gzip] {
+            let mut buffer = ByteVec(Vec::new());
+            pack.save(&mut buffer, compression)
+                .expect("Saving must work");
+            let loaded =
+                ModPack::load(buffer.0.as_slice(), "pack".to_owned()).expect("Loading must work");
+            assert_eq!(loaded.mods, pack.mods);
+            assert_eq!(loaded.settings, pack.settings);
+            assert_eq!(loaded.version(), CURRENT_VERSION);
+        }
+    }
+
+    #[test]
+    fn mods_only() {
+        let pair = ModSettingPair {
+            current: ModSettingValue::Bool(true),
+            next: ModSettingValue::Bool(true),
+        };
+        let mut values = HashMap::new();
+        values.insert("a.setting".to_owned(), pair.clone());
+        let mut settings = ModSettings {
+            grouped: ModSettings::compute_grouped(&values),
+            values,
+            ..Default::default()
+        };
+        settings.grouped.include_all(true);
+        let pack = ModPack::new(
+            "pack".to_owned(),
+            "pack".to_owned(),
+            &["a".to_owned()],
+            &settings,
+        )
+        .without_settings();
+        let mut buffer = ByteVec(Vec::new());
+        pack.save(&mut buffer, PackCompression::None)
+            .expect("Saving must work");
+        let loaded =
+            ModPack::load(buffer.0.as_slice(), "pack".to_owned()).expect("Loading must work");
+        assert!(loaded.mods_only());
+        assert!(loaded.settings.values.is_empty());
+        assert_eq!(loaded.mods, ["a"]);
+
+        let mut list = ModListConfig {
+            mods: vec![normal_mod("a", false)],
+            ..Default::default()
+        };
+        let mut live = HashMap::new();
+        live.insert("a.setting".to_owned(), pair);
+        list.mod_settings.values = live.clone();
+        let result = loaded.apply(
+            &mut list,
+            &HashSet::from(["a".to_owned()]),
+            ModApplyMode::Replace,
+            SettingsApplyMode::Overwrite,
+        );
+        assert_eq!(result.enabled, ["a"]);
+        assert_eq!(list.mod_settings.values, live);
+    }
+
+    #[test]
+    fn load_v0() {
+        let mut buffer = ByteVec(Vec::new());
+        buffer.write_le::<usize>(0).expect("Writing must work");
+        buffer
+            .write_str::<usize>("old", Little)
+            .expect("Writing must work");
+        buffer.write_le::<usize>(1).expect("Writing must work");
+        buffer
+            .write_str::<usize>("a", Little)
+            .expect("Writing must work");
+        buffer.write_le::<usize>(0).expect("Writing must work");
+        let loaded =
+            ModPack::load(buffer.0.as_slice(), "old".to_owned()).expect("Loading must work");
+        assert_eq!(loaded.version(), 0);
+        assert!(loaded.is_outdated());
+        assert!(!loaded.mods_only());
+        assert_eq!(loaded.mods, ["a"]);
+    }
+
+    #[test]
+    fn disabled_by_apply() {
+        let pack = ModPack::new(
+            "pack".to_owned(),
+            "pack".to_owned(),
+            &["a".to_owned(), "b".to_owned()],
+            &ModSettings::default(),
+        );
+        let enabled = ["a", "c", "d"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<HashSet<_>>();
+        assert_eq!(pack.disabled_by_apply(&enabled, ModApplyMode::Replace), 2);
+        assert_eq!(pack.disabled_by_apply(&enabled, ModApplyMode::Add), 0);
+        assert_eq!(
+            pack.disabled_by_apply(&HashSet::new(), ModApplyMode::Replace),
+            0
+        );
+    }
+
+    #[test]
+    fn validate() {
+        let mut values = HashMap::new();
+        values.insert(
+            "".to_owned(),
+            ModSettingPair {
+                current: ModSettingValue::None,
+                next: ModSettingValue::None,
+            },
+        );
+        values.insert(
+            "a.mixed".to_owned(),
+            ModSettingPair {
+                current: ModSettingValue::Bool(true),
+                next: ModSettingValue::Number(1.0),
+            },
+        );
+        values.insert(
+            "a.fine".to_owned(),
+            ModSettingPair {
+                current: ModSettingValue::None,
+                next: ModSettingValue::Number(1.0),
+            },
+        );
+        let pack = ModPack::new(
+            "pack".to_owned(),
+            "pack".to_owned(),
+            &[],
+            &ModSettings {
+                values,
+                ..Default::default()
+            },
+        );
+        assert_eq!(
+            pack.validate(),
+            [
+                "A setting has an empty key",
+                "Setting a.mixed has current type 1 but next type 2"
+            ]
+        );
+    }
+
+    #[test]
+    fn sort_by_order() {
+        let mut packs = ["new", "b", "a", "other"]
+            .map(|e| ModPack::new(e.to_owned(), e.to_owned(), &[], &Default::default()))
+            .to_vec();
+        super::sort_by_order(
+            &mut packs,
+            &["a".to_owned(), "gone".to_owned(), "b".to_owned()],
+        );
+        assert_eq!(
+            packs.iter().map(|e| e.file_name()).collect::<Vec<_>>(),
+            ["a", "b", "new", "other"]
+        );
+    }
+
+    #[test]
+    fn apply_orders_mods() {
+        let mut translation = normal_mod("translation", false);
+        translation.kind = ModKind::Translation;
+        let mut list = ModListConfig {
+            mods: vec![
+                normal_mod("a", false),
+                normal_mod("b", true),
+                translation,
+                normal_mod("c", false),
+                normal_mod("d", true),
+                normal_mod("e", false),
+            ],
+            ..Default::default()
+        };
+        let pack = ModPack::new(
+            "pack".to_owned(),
+            "pack".to_owned(),
+            &["e", "c", "missing", "a", "translation"].map(|e| e.to_owned()),
+            &Default::default(),
+        );
+        let result = pack.apply(
+            &mut list,
+            &HashSet::new(),
+            ModApplyMode::Replace,
+            SettingsApplyMode::ModsOnly,
+        );
+        assert_eq!(result.enabled, ["a", "c", "e"]);
+        assert_eq!(result.disabled, ["b", "d"]);
+        assert_eq!(result.missing, ["missing"]);
+        assert_eq!(result.summary(), "Enabled 3 mods and disabled 2");
+        let order = list
+            .mods
+            .iter()
+            .map(|e| {
+                let enabled = match e.kind {
+                    ModKind::Normal(NormalMod { enabled }) => enabled,
+                    _ => false,
+                };
+                (e.id.as_str(), enabled)
+            })
+            .collect::<Vec<_>>();
+        // enabled mods take the slots of enabled mods in pack order, everything else stays put
+        assert_eq!(
+            order,
+            [
+                ("e", true),
+                ("b", false),
+                ("translation", false),
+                ("c", true),
+                ("d", false),
+                ("a", true),
+            ]
+        );
+    }
+
+    #[test]
+    fn apply_additive() {
+        let mut list = ModListConfig {
+            mods: vec![
+                normal_mod("a", false),
+                normal_mod("b", true),
+                normal_mod("c", false),
+                normal_mod("d", true),
+            ],
+            ..Default::default()
+        };
+        let pack = ModPack::new(
+            "pack".to_owned(),
+            "pack".to_owned(),
+            &["c", "a"].map(|e| e.to_owned()),
+            &Default::default(),
+        );
+        let result = pack.apply(
+            &mut list,
+            &HashSet::new(),
+            ModApplyMode::Add,
+            SettingsApplyMode::ModsOnly,
+        );
+        assert_eq!(result.enabled, ["a", "c"]);
+        assert!(result.disabled.is_empty());
+        let order = list
+            .mods
+            .iter()
+            .map(|e| {
+                (
+                    e.id.as_str(),
+                    matches!(e.kind, ModKind::Normal(NormalMod { enabled: true })),
+                )
+            })
+            .collect::<Vec<_>>();
+        // the pack's mods swap into pack order, the mods that were already on keep their place
+        assert_eq!(order, [("c", true), ("b", true), ("a", true), ("d", true)]);
+    }
+
+    #[test]
+    fn apply_updates_grouped() {
+        let pair = ModSettingPair {
+            current: ModSettingValue::Bool(true),
+            next: ModSettingValue::Bool(true),
+        };
+        let mut existing = HashMap::new();
+        existing.insert("a.old".to_owned(), pair.clone());
+        existing.insert("a.picked".to_owned(), pair.clone());
+        let mut list = ModListConfig {
+            mod_settings: ModSettings {
+                grouped: ModSettings::compute_grouped(&existing),
+                values: existing,
+                ..Default::default()
+            },
+            ..Default::default()
+        };
+        list.mod_settings
+            .grouped
+            .apply_set(&HashSet::from(["a.picked".to_owned()]), "".to_owned());
+        let mut pack_values = HashMap::new();
+        pack_values.insert("a.new".to_owned(), pair);
+        let pack = ModPack::new(
+            "pack".to_owned(),
+            "pack".to_owned(),
+            &["a".to_owned()],
+            &ModSettings {
+                values: pack_values,
+                ..Default::default()
+            },
+        );
+        let result = pack.apply(
+            &mut list,
+            &HashSet::from(["a".to_owned()]),
+            ModApplyMode::Replace,
+            SettingsApplyMode::Overwrite,
+        );
+        assert_eq!(result.settings_added, ["a.new"]);
+        assert!(result.settings_overwritten.is_empty());
+        assert_eq!(result.summary(), "Added 1 setting");
+        assert_eq!(list.mod_settings.mod_ids, HashSet::from(["a".to_owned()]));
+        let grouped = &mut list.mod_settings.grouped;
+        assert!(grouped.to_set().contains("a.new"));
+        // what was picked before applying is still picked
+        assert!(grouped.to_set().contains("a.picked"));
+        assert!(!grouped.to_set().contains("a.old"));
+        grouped.include_all(true);
+        assert!(grouped.to_set().contains("a.old"));
+    }
+
+    #[test]
+    fn include_matching() {
+        let mut values = HashMap::new();
+        for key in [
+            "a.speed",
+            "a.colour",
+            "b.speed",
+            "b.extra.speed",
+            "speedy.x",
+        ] {
+            values.insert(
+                key.to_owned(),
+                ModSettingPair {
+                    current: ModSettingValue::Bool(true),
+                    next: ModSettingValue::Bool(true),
+                },
+            );
+        }
+        let mut grouped = ModSettings::compute_grouped(&values);
+        assert!(grouped.matches_filter("", "extra"));
+        assert!(!grouped.matches_filter("", "missing"));
+        grouped.include_matching("", "speed", true);
+        let expected = ["a.speed", "b.speed", "b.extra.speed", "speedy.x"]
+            .into_iter()
+            .map(str::to_owned)
+            .collect::<HashSet<_>>();
+        assert_eq!(grouped.to_set(), expected);
+    }
+
+    #[test]
+    fn include_type() {
+        let mut values = HashMap::new();
+        for (key, value) in [
+            ("a.toggle", ModSettingValue::Bool(true)),
+            ("a.count", ModSettingValue::Number(2.0)),
+            ("b.nested.flag", ModSettingValue::Bool(false)),
+            ("b.text", ModSettingValue::String("blob".to_owned())),
+        ] {
+            values.insert(
+                key.to_owned(),
+                ModSettingPair {
+                    current: value.clone(),
+                    next: value,
+                },
+            );
+        }
+        let mut grouped = ModSettings::compute_grouped(&values);
+        grouped.include_type(1, true);
+        assert_eq!(
+            grouped.to_set(),
+            HashSet::from(["a.toggle".to_owned(), "b.nested.flag".to_owned()])
+        );
+        grouped.include_all(true);
+        grouped.include_type(3, false);
+        assert!(!grouped.to_set().contains("b.text"));
+        assert_eq!(grouped.to_set().len(), 3);
+    }
+
+    #[test]
+    fn truncated_file() {
+        let mut buffer = ByteVec(Vec::new());
+        compress_file(&mut buffer, &[b'a'; 100]).expect("Compressing must work");
+        buffer.0.pop();
+        let file_len = buffer.0.len();
+        let error = decompress_file(&mut buffer, file_len).expect_err("Decompressing must fail");
+        assert!(format!("{error}").contains("truncated"));
+    }
+
+    #[test]
+    fn tiny_buffers() {
+        // fastlz can't take under 16 bytes, make sure nothing about working around that leaks into the output
+        for len in 0..=20 {
+            for value in [
+                vec![0; len],
+                vec![b'a'; len],
+                (0..len as u8).collect::<Vec<_>>(),
+            ] {
+                let mut buffer = ByteVec(Vec::new());
+                compress_file(&mut buffer, &value).expect("Compressing must work");
+                let file_len = buffer.0.len();
+                assert_eq!(
+                    decompress_file(&mut buffer, file_len).expect("Decompressing must work"),
+                    value,
+                    "Round trip of {len} bytes"
+                );
+            }
+        }
+    }
 
     #[test]
     fn compress() {
@@ -661,4 +1667,18 @@ mod test {
         let decompressed = decompress_file(&mut buffer, len).expect("Loading must work");
         assert_eq!(s, decompressed);
     }
+
+    #[test]
+    fn compression_stats() {
+        let mut buffer = ByteVec(Vec::new());
+        let stats = compress_file(&mut buffer, &[b'a'; 100]).expect("Compressing must work");
+        assert!(!stats.stored());
+        assert_eq!(stats.decompressed, 100);
+        assert_eq!(stats.compressed + 8, buffer.0.len());
+
+        let mut buffer = ByteVec(Vec::new());
+        let stats = compress_file(&mut buffer, b"tiny").expect("Compressing must work");
+        assert!(stats.stored());
+        assert!(stats.describe().contains("stored uncompressed"));
+    }
 }
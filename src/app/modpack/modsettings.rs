@@ -1,14 +1,17 @@
 use quickcheck::{Arbitrary, Gen};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, HashSet},
     io::{Read, Write},
     iter::{empty, zip},
 };
 
-use anyhow::{anyhow, Context};
-use egui::Ui;
+use anyhow::{anyhow, bail, Context};
+use egui::{DragValue, Ui};
 
-use crate::ext::{ByteReaderExt, ByteWriterExt, Endianness::Big};
+use crate::{
+    ext::{ByteReaderExt, ByteWriterExt, Endianness::Big},
+    icons::YELLOW,
+};
 
 #[derive(Clone, Debug, PartialEq)]
 pub enum ModSettingValue {
@@ -22,20 +25,81 @@ pub enum ModSettingValue {
     String(String),
 }
 
+/// `current` is what the game is running with, `next` is what it will use after a restart
+/// The two should always have the same type, except that either may be `None` if Noita hasn't set it yet.
+/// A pair that changes type between them confuses Noita, so `next` should only be changed through [`ModSettingPair::set_next`]
 #[derive(Clone, Debug, PartialEq)]
 pub struct ModSettingPair {
     pub current: ModSettingValue,
     pub next: ModSettingValue,
 }
 
-#[derive(Clone, Debug, PartialEq, Default)]
+#[derive(Clone, Debug, Default)]
 pub struct ModSettings {
     pub values: HashMap<String, ModSettingPair>,
+    /// a view of `values` for the ui, rebuilt with [`ModSettings::recompute_grouped`] whenever they change
     pub grouped: super::ModSettingsGroup,
+    /// ids of mods with at least one value, rebuilt along with `grouped` so searching doesn't redo it every frame
+    pub mod_ids: HashSet<String>,
+    /// sizes from the last time this was loaded from or saved to a file, `None` if it never was
+    pub compression: Option<super::CompressionStats>,
+}
+
+impl ModSettings {
+    /// every setting by its full key, in no particular order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, &ModSettingPair)> {
+        self.values.iter().map(|(key, pair)| (key.as_str(), pair))
+    }
+
+    pub fn len(&self) -> usize {
+        self.values.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.values.is_empty()
+    }
+}
+
+/// `grouped` is derived from `values` (plus ui state), so it isn't compared
+impl PartialEq for ModSettings {
+    fn eq(&self, other: &Self) -> bool {
+        self.values == other.values
+    }
 }
 
 impl ModSettingPair {
+    /// whether the pair upholds the type invariant described on [`ModSettingPair`]
+    pub fn is_consistent(&self) -> bool {
+        self.current == ModSettingValue::None
+            || self.next == ModSettingValue::None
+            || self.current.type_int() == self.next.type_int()
+    }
+
+    /// refuses values that would leave the pair with two different types
+    pub fn set_next(&mut self, next: ModSettingValue) -> anyhow::Result<()> {
+        let candidate = ModSettingPair {
+            current: self.current.clone(),
+            next,
+        };
+        if !candidate.is_consistent() {
+            bail!(
+                "Can't set next value to {:?} when the current value is {:?}",
+                candidate.next,
+                self.current
+            );
+        }
+        *self = candidate;
+        Ok(())
+    }
+
     pub fn render(&self, ui: &mut Ui) {
+        if self.current == ModSettingValue::None && self.next == ModSettingValue::None {
+            ui.label("Uninitialized, Noita hasn't given this setting a value yet");
+            return;
+        }
+        if !self.is_consistent() {
+            ui.colored_label(YELLOW, "Current and next have different types");
+        }
         ui.horizontal(|ui| {
             ui.label("Current");
             self.current.render(ui)
@@ -111,6 +175,19 @@ impl ModSettingValue {
         }
     }
 
+    /// edits the value in place without changing its type, returns if it changed
+    pub fn edit(&mut self, ui: &mut Ui) -> bool {
+        match self {
+            ModSettingValue::None => {
+                ui.label("Unset values can't be edited");
+                false
+            }
+            ModSettingValue::Bool(val) => ui.checkbox(val, "").changed(),
+            ModSettingValue::Number(val) => ui.add(DragValue::new(val)).changed(),
+            ModSettingValue::String(val) => ui.text_edit_singleline(val).changed(),
+        }
+    }
+
     pub fn render(&self, ui: &mut Ui) {
         match self {
             ModSettingValue::None => ui.code("None()"),
@@ -165,6 +242,15 @@ mod test {
         true
     }
 
+    #[quickcheck]
+    fn incompressible_buffer(value: Vec<u8>) -> bool {
+        // random bytes are the worst case for fastlz, so they push the output size bound the hardest
+        let mut buffer = ByteVec(Vec::new());
+        compress_file(&mut buffer, &value).expect("Saving errored");
+        let len = buffer.0.len();
+        value == decompress_file(&mut buffer, len).expect("Loading errored")
+    }
+
     #[quickcheck]
     fn save_load_buffer(value: String) -> bool {
         let bytes = value.as_bytes();
@@ -174,6 +260,70 @@ mod test {
         bytes == decompress_file(&mut buffer, len).expect("Loading errored")
     }
 
+    #[test]
+    fn edited_numbers_round_trip() {
+        let special = [
+            -0.0,
+            0.0,
+            f64::MIN_POSITIVE / 2.0, // subnormal
+            -f64::MIN_POSITIVE / 4.0,
+            f64::MAX,
+            f64::MIN,
+            f64::INFINITY,
+            f64::NEG_INFINITY,
+            f64::NAN,
+            1.0 / 3.0,
+        ];
+        let mut values = HashMap::new();
+        for (i, number) in special.iter().enumerate() {
+            let mut pair = ModSettingPair {
+                current: ModSettingValue::Number(1.0),
+                next: ModSettingValue::Number(1.0),
+            };
+            // the same path the settings editor uses
+            pair.set_next(ModSettingValue::Number(*number))
+                .expect("Same type must be allowed");
+            values.insert(format!("mod.number{i}"), pair);
+        }
+        let settings = ModSettings {
+            values,
+            ..Default::default()
+        };
+        let mut buffer = ByteVec(Vec::new());
+        settings.save(&mut buffer).expect("Saving must work");
+        let len = buffer.0.len();
+        let loaded = ModSettings::load(&mut buffer, len).expect("Loading must work");
+        for (i, number) in special.iter().enumerate() {
+            let ModSettingValue::Number(next) = loaded.values[&format!("mod.number{i}")].next
+            else {
+                panic!("Number {i} changed type");
+            };
+            // compared as bits as -0 == 0 and NaN != NaN
+            assert_eq!(next.to_bits(), number.to_bits(), "Number {i}");
+        }
+    }
+
+    #[test]
+    fn set_next_keeps_types_consistent() {
+        let mut pair = ModSettingPair {
+            current: ModSettingValue::Bool(false),
+            next: ModSettingValue::Bool(false),
+        };
+        pair.set_next(ModSettingValue::Bool(true))
+            .expect("Same type must be allowed");
+        assert!(pair.set_next(ModSettingValue::Number(1.0)).is_err());
+        assert_eq!(pair.next, ModSettingValue::Bool(true));
+
+        let mut unset = ModSettingPair {
+            current: ModSettingValue::None,
+            next: ModSettingValue::None,
+        };
+        unset
+            .set_next(ModSettingValue::String("a".to_owned()))
+            .expect("Unset current allows any type");
+        assert!(unset.is_consistent());
+    }
+
     #[test]
     fn settings() {
         let mut map = HashMap::new();
@@ -0,0 +1,31 @@
+use std::{collections::HashMap, fs, path::Path};
+
+use anyhow::Context;
+
+/// one name per line, a missing file is an empty order
+pub fn load(path: &Path) -> anyhow::Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    Ok(fs::read_to_string(path)
+        .context(format!("Reading order file {}", path.display()))?
+        .lines()
+        .filter(|e| !e.is_empty())
+        .map(|e| e.to_owned())
+        .collect())
+}
+
+pub fn save<'a>(path: &Path, names: impl IntoIterator<Item = &'a str>) -> anyhow::Result<()> {
+    let content = names.into_iter().collect::<Vec<_>>().join("\n");
+    fs::write(path, content).context(format!("Writing order file {}", path.display()))
+}
+
+/// items named in `order` come first in that order, the rest keep their relative order after them
+pub fn sort<T>(items: &mut [T], order: &[String], name: impl Fn(&T) -> &str) {
+    let positions = order
+        .iter()
+        .enumerate()
+        .map(|(i, e)| (e.as_str(), i))
+        .collect::<HashMap<_, _>>();
+    items.sort_by_key(|e| positions.get(name(e)).copied().unwrap_or(usize::MAX));
+}
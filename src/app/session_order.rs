@@ -0,0 +1,73 @@
+use std::{fs, path::Path};
+
+use anyhow::Context;
+
+use super::name_order;
+use crate::r#mod::Mod;
+
+/// the load order the manager was closed with while it had unsaved changes, one mod id per line
+/// it's only kept until the next start, the game's config is what gets loaded after a save
+pub const SESSION_ORDER_FILE: &str = "./session_order.txt";
+
+/// a missing file means the last session ended saved
+pub fn load(path: &Path) -> anyhow::Result<Option<Vec<String>>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    name_order::load(path)
+        .map(Some)
+        .context("Loading session order")
+}
+
+pub fn save(path: &Path, mods: &[Mod]) -> anyhow::Result<()> {
+    name_order::save(path, mods.iter().map(|e| e.id.as_str())).context("Saving session order")
+}
+
+pub fn clear(path: &Path) -> anyhow::Result<()> {
+    if !path.exists() {
+        return Ok(());
+    }
+    fs::remove_file(path).context(format!("Removing session order {}", path.display()))
+}
+
+/// mods in `order` come first in that order, new mods keep their relative order after them
+/// ids of mods that aren't installed anymore are skipped, returns whether anything moved
+pub fn restore(mods: &mut [Mod], order: &[String]) -> bool {
+    let before = mods.iter().map(|e| e.id.clone()).collect::<Vec<_>>();
+    name_order::sort(mods, order, |e| e.id.as_str());
+    mods.iter().zip(before.iter()).any(|(a, b)| &a.id != b)
+}
+
+#[cfg(test)]
+mod test {
+    use std::fs;
+
+    use super::{load, restore, save};
+    use crate::r#mod::{Mod, ModKind, NormalMod};
+
+    fn test_mod(id: &str) -> Mod {
+        Mod::test(id, ModKind::Normal(NormalMod { enabled: false }))
+    }
+
+    fn ids(mods: &[Mod]) -> Vec<&str> {
+        mods.iter().map(|e| e.id.as_str()).collect()
+    }
+
+    #[test]
+    fn restore_order() {
+        let path =
+            std::env::temp_dir().join(format!("noita_modman_session_order_{}", std::process::id()));
+        let saved = ["c", "gone", "a", "b"].map(test_mod);
+        save(&path, &saved).expect("Saving must work");
+        let order = load(&path)
+            .expect("Loading must work")
+            .expect("The file was just written");
+        fs::remove_file(&path).expect("Cleaning up must work");
+        assert!(load(&path).expect("Loading must work").is_none());
+
+        let mut mods = ["a", "new", "b", "c", "newer"].map(test_mod);
+        assert!(restore(&mut mods, &order));
+        assert_eq!(ids(&mods), ["c", "a", "b", "new", "newer"]);
+        assert!(!restore(&mut mods, &order));
+    }
+}
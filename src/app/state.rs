@@ -0,0 +1,199 @@
+use std::{
+    fs::{self, File},
+    io::Write,
+    path::Path,
+    str::FromStr,
+};
+
+use anyhow::{bail, Context};
+use serde::{Deserialize, Serialize};
+
+use super::modpack::{ModApplyMode, PackCompression, SettingsApplyMode};
+use crate::r#mod::conditional::DEFAULT_RECENT_DAYS;
+
+pub const STATE_FILE: &str = "./state.toml";
+
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum SwapSlot {
+    A,
+    B,
+}
+
+impl SwapSlot {
+    pub fn other(self) -> SwapSlot {
+        match self {
+            SwapSlot::A => SwapSlot::B,
+            SwapSlot::B => SwapSlot::A,
+        }
+    }
+}
+
+/// Two modpacks that can be toggled between with a single click
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct QuickSwap {
+    /// file name of the pack in slot A
+    pub a: Option<String>,
+    /// file name of the pack in slot B
+    pub b: Option<String>,
+    /// the slot that was applied last, swapping applies the other one
+    pub active: Option<SwapSlot>,
+    pub save_after_swap: bool,
+}
+
+impl QuickSwap {
+    pub fn slot(&self, slot: SwapSlot) -> Option<&String> {
+        match slot {
+            SwapSlot::A => self.a.as_ref(),
+            SwapSlot::B => self.b.as_ref(),
+        }
+    }
+
+    pub fn slot_mut(&mut self, slot: SwapSlot) -> &mut Option<String> {
+        match slot {
+            SwapSlot::A => &mut self.a,
+            SwapSlot::B => &mut self.b,
+        }
+    }
+}
+
+/// How the panels are set up when the manager opens
+#[derive(Copy, Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+pub enum StartupLayout {
+    /// however they were left last time
+    #[default]
+    Remember,
+    /// settings panel hidden
+    Mods,
+    /// settings panel open and taking most of the window
+    Settings,
+}
+
+impl StartupLayout {
+    pub const ALL: [StartupLayout; 3] = [
+        StartupLayout::Remember,
+        StartupLayout::Mods,
+        StartupLayout::Settings,
+    ];
+
+    /// also what the `--layout=` command line flag takes, lowercased
+    pub fn label(self) -> &'static str {
+        match self {
+            StartupLayout::Remember => "Remember",
+            StartupLayout::Mods => "Mods",
+            StartupLayout::Settings => "Settings",
+        }
+    }
+
+    pub fn description(self) -> &'static str {
+        match self {
+            StartupLayout::Remember => "Open the panels how they were left",
+            StartupLayout::Mods => "Open with the settings panel hidden",
+            StartupLayout::Settings => "Open with a wide settings panel",
+        }
+    }
+}
+
+impl FromStr for StartupLayout {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> anyhow::Result<StartupLayout> {
+        match StartupLayout::ALL
+            .into_iter()
+            .find(|e| e.label().eq_ignore_ascii_case(s))
+        {
+            Some(layout) => Ok(layout),
+            None => bail!("Unknown layout {s}, expected remember, mods or settings"),
+        }
+    }
+}
+
+/// Manager preferences that aren't part of the game config, survives restarts
+#[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PersistentState {
+    pub quick_swap: QuickSwap,
+    pub mod_apply_mode: ModApplyMode,
+    pub settings_apply_mode: SettingsApplyMode,
+    /// used for packs we write, loading detects it
+    pub pack_compression: PackCompression,
+    /// exported packs only have the mod list, snapshots always have settings
+    pub export_without_settings: bool,
+    pub show_hidden: bool,
+    /// only list enabled mods, on top of the search
+    pub enabled_only: bool,
+    /// mark enabled mods in the list with a green edge
+    pub tint_enabled: bool,
+    /// smaller mod list rows without the source and kind icons
+    pub compact: bool,
+    /// mod ids after their names, for matching the list against the mod config
+    pub show_ids: bool,
+    /// include workshop ids when copying the load order
+    pub report_workshop_ids: bool,
+    pub settings_panel_open: bool,
+    /// off by default as it writes the game's files often
+    pub auto_save: bool,
+    /// which mod dirs are loaded, the config entries of one that isn't are kept as they are
+    pub load_local: bool,
+    pub load_workshop: bool,
+    /// unix seconds of when the manager last started, workshop mods changed after it are marked updated
+    pub last_run: Option<u64>,
+    /// how many copies of the mod config are kept in the backups dir, 0 turns backups off
+    pub config_backups: usize,
+    /// how many days back the #recent search looks
+    pub recent_days: u64,
+    /// can be overridden for one run with `--layout=`
+    pub startup_layout: StartupLayout,
+    /// sizes of the resizable panels, `None` uses egui's default
+    pub settings_panel_width: Option<f32>,
+    pub modpack_panel_height: Option<f32>,
+}
+
+impl Default for PersistentState {
+    fn default() -> Self {
+        Self {
+            quick_swap: Default::default(),
+            mod_apply_mode: Default::default(),
+            settings_apply_mode: Default::default(),
+            pack_compression: Default::default(),
+            export_without_settings: false,
+            show_hidden: false,
+            enabled_only: false,
+            tint_enabled: true,
+            compact: false,
+            show_ids: false,
+            report_workshop_ids: true,
+            settings_panel_open: true,
+            startup_layout: Default::default(),
+            auto_save: false,
+            load_local: true,
+            load_workshop: true,
+            recent_days: DEFAULT_RECENT_DAYS,
+            config_backups: 10,
+            last_run: None,
+            settings_panel_width: None,
+            modpack_panel_height: None,
+        }
+    }
+}
+
+impl PersistentState {
+    /// a missing file is not an error, it just means we haven't saved yet
+    pub fn load(path: &Path) -> anyhow::Result<PersistentState> {
+        if !path.exists() {
+            return Ok(Default::default());
+        }
+        let content =
+            fs::read_to_string(path).context(format!("Reading state file {}", path.display()))?;
+        toml::from_str(&content).context(format!("Parsing state file {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let content = toml::to_string(self).context("Serializing state")?;
+        let mut file =
+            File::create(path).context(format!("Creating state file {}", path.display()))?;
+        file.write_all(content.as_bytes())
+            .context("Writing state file")?;
+        file.flush().context("Flushing state file")
+    }
+}
@@ -0,0 +1,50 @@
+use std::{collections::BTreeMap, fs, path::Path};
+
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+
+use crate::r#mod::{Mod, ModUserData};
+
+pub const USER_DATA_FILE: &str = "./mod_user_data.toml";
+
+/// Per mod data the user has set, by mod id. Mods that aren't installed keep their data
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UserData {
+    pub mods: BTreeMap<String, ModUserData>,
+}
+
+impl UserData {
+    /// a missing file is not an error, it just means nothing has been set yet
+    pub fn load(path: &Path) -> anyhow::Result<UserData> {
+        if !path.exists() {
+            return Ok(Default::default());
+        }
+        let content = fs::read_to_string(path)
+            .context(format!("Reading mod user data {}", path.display()))?;
+        toml::from_str(&content).context(format!("Parsing mod user data {}", path.display()))
+    }
+
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        let content = toml::to_string(self).context("Serializing mod user data")?;
+        fs::write(path, content).context(format!("Writing mod user data {}", path.display()))
+    }
+
+    pub fn attach(&self, mods: &mut [Mod]) {
+        for nmod in mods.iter_mut() {
+            if let Some(data) = self.mods.get(&nmod.id) {
+                nmod.user = data.clone();
+                nmod.apply_kind_override();
+            }
+        }
+    }
+
+    /// copies the mod's data in, so it gets saved
+    pub fn update(&mut self, nmod: &Mod) {
+        if nmod.user.is_empty() {
+            self.mods.remove(&nmod.id);
+        } else {
+            self.mods.insert(nmod.id.clone(), nmod.user.clone());
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use egui::{
     collapsing_header::{paint_default_icon, CollapsingState},
-    epaint, pos2, CollapsingResponse, Id, InnerResponse, Rect, Response, Sense, StrokeKind, Ui,
-    WidgetInfo, WidgetType,
+    epaint, pos2, CollapsingResponse, EventFilter, Id, InnerResponse, Key, Rect, Response, Sense,
+    StrokeKind, Ui, WidgetInfo, WidgetType,
 };
 
 struct Prepared<T> {
@@ -44,6 +44,12 @@ impl<T> CollapsingUi<T> {
         }
     }
 
+    /// `Some` forces the open state, which is remembered after
+    pub fn open(mut self, open: Option<bool>) -> Self {
+        self.open = open;
+        self
+    }
+
     fn begin(self, ui: &mut Ui) -> Prepared<T> {
         assert!(
             ui.layout().main_dir().is_vertical(),
@@ -90,6 +96,27 @@ impl<T> CollapsingUi<T> {
             } else if header_response.clicked() {
                 state.toggle(ui);
                 header_response.mark_changed();
+            } else if header_response.has_focus() {
+                // egui moves focus with all the arrows, up and down still do but left and right close and open
+                ui.memory_mut(|mem| {
+                    mem.set_focus_lock_filter(
+                        id,
+                        EventFilter {
+                            horizontal_arrows: true,
+                            ..Default::default()
+                        },
+                    )
+                });
+                let (left, right) = ui.input(|i| {
+                    (
+                        i.key_pressed(Key::ArrowLeft),
+                        i.key_pressed(Key::ArrowRight),
+                    )
+                });
+                if (left && state.is_open()) || (right && !state.is_open()) {
+                    state.toggle(ui);
+                    header_response.mark_changed();
+                }
             }
 
             header_response.widget_info(|| {
@@ -108,6 +135,15 @@ impl<T> CollapsingUi<T> {
                 ));
             }
 
+            if header_response.has_focus() {
+                ui.painter().rect_stroke(
+                    rect.expand(visuals.expansion + 1.0),
+                    visuals.corner_radius,
+                    ui.visuals().selection.stroke,
+                    StrokeKind::Outside,
+                );
+            }
+
             if selected || selectable && (header_response.hovered() || header_response.has_focus())
             {
                 let rect = rect.expand(visuals.expansion);
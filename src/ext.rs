@@ -4,7 +4,7 @@ use std::{
     io::{Read, Write},
 };
 
-use anyhow::{anyhow, Context};
+use anyhow::{anyhow, bail, Context};
 use bytemuck::{AnyBitPattern, NoUninit};
 
 pub enum Endianness {
@@ -15,10 +15,27 @@ pub enum Endianness {
 pub trait ByteReaderExt {
     fn read_le<T: AnyBitPattern>(&mut self) -> anyhow::Result<T>;
     fn read_be<T: AnyBitPattern>(&mut self) -> anyhow::Result<T>;
+    /// the raw bytes of a length prefixed string
+    fn read_str_bytes<T: AnyBitPattern>(
+        &mut self,
+        endianness: Endianness,
+    ) -> anyhow::Result<Vec<u8>>
+    where
+        <T as TryInto<usize>>::Error: Debug,
+        usize: TryFrom<T>;
+    /// use this for anything that gets written back, like ids and setting keys or values, so the bytes don't change
     fn read_str<T: AnyBitPattern>(&mut self, endianness: Endianness) -> anyhow::Result<String>
     where
         <T as TryInto<usize>>::Error: Debug,
         usize: TryFrom<T>;
+    /// invalid utf8 is replaced rather than being an error, only for strings that are just displayed like modpack names
+    fn read_str_lossy<T: AnyBitPattern>(
+        &mut self,
+        endianness: Endianness,
+    ) -> anyhow::Result<String>
+    where
+        <T as TryInto<usize>>::Error: Debug,
+        usize: TryFrom<T>;
 }
 
 impl<R: Read> ByteReaderExt for R {
@@ -30,30 +47,53 @@ impl<R: Read> ByteReaderExt for R {
             .map_err(|e| anyhow!("Failed to try from bytes {e}"))?))
     }
 
-    fn read_str<T: AnyBitPattern>(&mut self, endianness: Endianness) -> anyhow::Result<String>
+    fn read_str_bytes<T: AnyBitPattern>(
+        &mut self,
+        endianness: Endianness,
+    ) -> anyhow::Result<Vec<u8>>
     where
         <T as TryInto<usize>>::Error: Debug,
-        T: TryInto<usize>,
+        usize: TryFrom<T>,
     {
         let len: usize = match endianness {
-            Endianness::Little => self
-                .read_le::<T>()
-                .context("Reading string length")?
-                .try_into()
-                .expect("Length must be able to be cast to usize"), // TODO: make this anyhow / comptime ideally
-            Endianness::Big => self
-                .read_be::<T>()
-                .context("Reading string length")?
-                .try_into()
-                .expect("Length must be able to be cast to usize"),
-        };
-
-        let mut buf = vec![0; len as usize];
-        self.read_exact(&mut buf).context("Reading string data")?;
-        Ok(String::from_utf8(buf.clone()).context(
-            // TODO: another wasteful clone
-            format!("Converting string data {:?} to utf8", buf),
-        )?)
+            Endianness::Little => self.read_le::<T>(),
+            Endianness::Big => self.read_be::<T>(),
+        }
+        .context("Reading string length")?
+        .try_into()
+        .map_err(|e| anyhow!("String length doesn't fit in a usize {e:?}"))?;
+
+        // a corrupt length can be huge, so read what is actually there rather than allocating `len` up front
+        let mut buf = Vec::new();
+        self.by_ref()
+            .take(len as u64)
+            .read_to_end(&mut buf)
+            .context("Reading string data")?;
+        if buf.len() != len {
+            bail!(
+                "String length prefix says {len} bytes but only {} were left, the length is probably corrupt",
+                buf.len()
+            );
+        }
+        Ok(buf)
+    }
+
+    fn read_str<T: AnyBitPattern>(&mut self, endianness: Endianness) -> anyhow::Result<String>
+    where
+        <T as TryInto<usize>>::Error: Debug,
+        usize: TryFrom<T>,
+    {
+        String::from_utf8(self.read_str_bytes::<T>(endianness)?)
+            .map_err(|e| anyhow!("Converting string data {:?} to utf8: {e}", e.as_bytes()))
+    }
+
+    fn read_str_lossy<T: AnyBitPattern>(&mut self, endianness: Endianness) -> anyhow::Result<String>
+    where
+        <T as TryInto<usize>>::Error: Debug,
+        usize: TryFrom<T>,
+    {
+        let bytes = self.read_str_bytes::<T>(endianness)?;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
     }
 
     fn read_be<T: AnyBitPattern>(&mut self) -> anyhow::Result<T> {
@@ -144,6 +184,34 @@ mod test {
     use crate::ext::ByteWriterExt;
     use crate::ext::Endianness::Little;
 
+    #[test]
+    fn corrupt_string_length() {
+        let mut buffer = ByteVec(Vec::new());
+        buffer
+            .write_le::<usize>(usize::MAX / 2)
+            .expect("Saving must work");
+        buffer.0.extend(b"short");
+        let error = buffer
+            .read_str::<usize>(Little)
+            .expect_err("Loading must fail");
+        assert!(format!("{error}").contains("probably corrupt"));
+    }
+
+    #[test]
+    fn lossy_string() {
+        let mut buffer = ByteVec(Vec::new());
+        buffer.write_le::<usize>(3).expect("Saving must work");
+        buffer.0.extend([b'a', 0xff, b'b']);
+        let mut strict = buffer.clone();
+        assert!(strict.read_str::<usize>(Little).is_err());
+        assert_eq!(
+            buffer
+                .read_str_lossy::<usize>(Little)
+                .expect("Loading must work"),
+            "a\u{fffd}b"
+        );
+    }
+
     #[quickcheck]
     fn save_load_string(value: String) -> bool {
         let mut buffer = ByteVec(Vec::new());
@@ -5,5 +5,10 @@ pub const TRANSLATION: char = '\u{1F4D5}';
 pub const GAMEMODE: char = '\u{1F30F}';
 pub const NORMAL: char = '\u{1F5A5}';
 pub const UNSAFE: char = '\u{26A0}';
+pub const LINK: char = '\u{1F517}';
+pub const UPDATED: char = '\u{2605}';
+pub const FOLDER: char = '\u{1F4C1}';
+pub const MOD_WORKSHOP: char = '\u{1F527}';
+pub const OVERRIDES: char = '\u{1F4DD}';
 
 pub const YELLOW: Color32 = Color32::from_rgb(255, 220, 40);
@@ -26,10 +26,14 @@ mod ext;
 mod icons;
 mod r#mod;
 use anyhow::Context;
-use app::{App, ProfilerInfo};
+use app::{App, ProfilerInfo, StartupLayout};
 use r#mod::Mod;
 
 fn main() -> anyhow::Result<()> {
+    let layout = std::env::args()
+        .find_map(|e| e.strip_prefix("--layout=").map(str::parse::<StartupLayout>))
+        .transpose()
+        .context("Parsing --layout")?;
     let mut content_str = String::new();
     let _ = &BufReader::new(
         File::open(Path::new("./Config.toml").to_path_buf()).context("Reading config file")?,
@@ -44,6 +48,8 @@ fn main() -> anyhow::Result<()> {
     #[cfg(feature = "profiler")]
     let profiler = ProfilerInfo {
         frame_counter: 0,
+        window_frames: 0,
+        window_start: std::time::Instant::now(),
         profiler: pprof::ProfilerGuardBuilder::default()
             .frequency(1000)
             .blocklist(&["libc", "libgcc", "pthread", "vdso"])
@@ -63,5 +69,5 @@ fn main() -> anyhow::Result<()> {
     )
     .context("Creating app")?;
 
-    app.run().context("Running app")
+    app.run(layout).context("Running app")
 }
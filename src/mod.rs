@@ -1,8 +1,15 @@
-use conditional::Condition;
-use egui::{Rect, RichText};
+use std::time::SystemTime;
+
+use conditional::{Condition, SearchContext};
+use egui::{Key, Rect, RichText};
+use serde::{Deserialize, Serialize};
 pub mod conditional;
+pub mod source;
 use crate::app::{UiSizedExt, SCALE};
-use crate::icons::{GAMEMODE, NORMAL, STEAM, TRANSLATION, UNSAFE, YELLOW};
+use crate::icons::{GAMEMODE, LINK, NORMAL, OVERRIDES, TRANSLATION, UNSAFE, UPDATED, YELLOW};
+
+/// what `load_mod` names mods whose mod.xml has no name
+pub const UNNAMED: &str = "unnamed";
 
 #[derive(Copy, Clone, Debug)]
 pub enum GitHost {
@@ -58,123 +65,544 @@ pub struct Mod {
     /// this is just needed for saving as we loaded it
     pub settings_fold_open: bool,
     pub tags: Option<Vec<String>>,
+    /// from workshop.xml, mod.xml doesn't have one
+    pub author: Option<String>,
+    pub user: ModUserData,
+    /// set when the mod couldn't be loaded, the rest is a placeholder built from its directory
+    pub load_error: Option<String>,
+    /// bytes on disk, measured in the background after loading so `None` until then
+    pub size: Option<u64>,
+    /// last change to the mod's directory, adding or updating files usually bumps it
+    pub modified: Option<SystemTime>,
+    /// vanilla files the mod replaces outright, from a scan for well known paths so it can miss some
+    pub vanilla_overrides: Vec<String>,
+    /// a workshop mod that changed since the manager last ran, see [`Mod::mark_updated`]
+    pub updated: bool,
+    /// what `load_mod` detected when the user has overridden the kind, `kind` is what the manager treats it as
+    pub overridden_from: Option<ModKind>,
+    /// other installs with the same id, like a local copy of a workshop mod, see [`Mod::use_copy`]
+    pub other_copies: Vec<ModCopy>,
+}
+
+/// Everything that is read from a mod's own directory, for an install of a mod that isn't the one in use
+#[derive(Clone, Debug)]
+pub struct ModCopy {
+    pub source: ModSource,
+    pub name: String,
+    pub description: String,
+    pub unsafe_api: bool,
+    pub tags: Option<Vec<String>>,
+    pub author: Option<String>,
+    pub load_error: Option<String>,
+    pub size: Option<u64>,
+    pub modified: Option<SystemTime>,
+    pub vanilla_overrides: Vec<String>,
+    pub updated: bool,
+}
+
+/// the id and what the user set are shared by every copy, so only the rest is kept
+impl From<Mod> for ModCopy {
+    fn from(nmod: Mod) -> ModCopy {
+        ModCopy {
+            source: nmod.source,
+            name: nmod.name,
+            description: nmod.description,
+            unsafe_api: nmod.unsafe_api,
+            tags: nmod.tags,
+            author: nmod.author,
+            load_error: nmod.load_error,
+            size: nmod.size,
+            modified: nmod.modified,
+            vanilla_overrides: nmod.vanilla_overrides,
+            updated: nmod.updated,
+        }
+    }
+}
+
+/// a workshop mod whose directory changed after `last_run`
+fn updated_since(
+    source: &ModSource,
+    modified: Option<SystemTime>,
+    last_run: Option<SystemTime>,
+) -> bool {
+    matches!(source, ModSource::Steam(_))
+        && matches!((modified, last_run), (Some(modified), Some(last_run)) if modified > last_run)
+}
+
+/// Things the user set on a mod which the game doesn't know about, kept in a sidecar file by mod id
+#[derive(Clone, Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ModUserData {
+    /// added on top of the workshop tags
+    pub tags: Vec<String>,
+    /// left out of the mod list unless showing hidden mods, it is still saved to the config like any other mod
+    pub hidden: bool,
+    /// free text for the user to remember things by, like why the mod is disabled
+    pub note: String,
+    /// for mods whose mod.xml says the wrong thing, `None` uses what it says
+    pub kind: Option<KindOverride>,
+}
+
+/// What the user can force a mod's kind to be
+#[derive(Copy, Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum KindOverride {
+    Normal,
+    Translation,
+    Gamemode,
+}
+
+impl KindOverride {
+    pub const ALL: [KindOverride; 3] = [
+        KindOverride::Normal,
+        KindOverride::Translation,
+        KindOverride::Gamemode,
+    ];
+
+    pub fn label(self) -> &'static str {
+        match self {
+            KindOverride::Normal => "Normal",
+            KindOverride::Translation => "Translation",
+            KindOverride::Gamemode => "Gamemode",
+        }
+    }
+}
+
+impl ModUserData {
+    /// empty data isn't worth saving
+    pub fn is_empty(&self) -> bool {
+        *self == Default::default()
+    }
 }
 
 #[derive(Clone, Debug)]
 pub struct ModRenderResponse {
     pub full_rect: Rect,
     pub text_rect: Rect,
-    pub text_hover: String,
 }
 
 impl Mod {
-    pub fn matches(&self, conditions: &[Condition]) -> bool {
+    /// the name, or the id for mods without one so they can be told apart, use this for showing and sorting
+    pub fn display_name(&self) -> &str {
+        if self.name == UNNAMED {
+            &self.id
+        } else {
+            &self.name
+        }
+    }
+
+    /// why the game won't load a mod with this id, it only takes ascii letters, numbers, `_` and `-`
+    pub fn id_problem(&self) -> Option<String> {
+        if self.id.is_empty() {
+            return Some("The mod id is empty".to_owned());
+        }
+        let mut bad = Vec::new();
+        for c in self.id.chars() {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                continue;
+            }
+            let shown = match c {
+                ' ' => "space".to_owned(),
+                c if c.is_whitespace() || c.is_control() => format!("{c:?}"),
+                c => c.to_string(),
+            };
+            if !bad.contains(&shown) {
+                bad.push(shown);
+            }
+        }
+        if bad.is_empty() {
+            return None;
+        }
+        Some(format!(
+            "The id {} has characters Noita doesn't allow ({}), rename its folder so the game loads it",
+            self.id,
+            bad.join(", ")
+        ))
+    }
+
+    /// swaps what was loaded from the directory with one of [`Mod::other_copies`], the mod config says which copy the game loads
+    pub fn use_copy(&mut self, index: usize) {
+        let copy = &mut self.other_copies[index];
+        std::mem::swap(&mut self.source, &mut copy.source);
+        std::mem::swap(&mut self.name, &mut copy.name);
+        std::mem::swap(&mut self.description, &mut copy.description);
+        std::mem::swap(&mut self.unsafe_api, &mut copy.unsafe_api);
+        std::mem::swap(&mut self.tags, &mut copy.tags);
+        std::mem::swap(&mut self.author, &mut copy.author);
+        std::mem::swap(&mut self.load_error, &mut copy.load_error);
+        std::mem::swap(&mut self.size, &mut copy.size);
+        std::mem::swap(&mut self.modified, &mut copy.modified);
+        std::mem::swap(&mut self.vanilla_overrides, &mut copy.vanilla_overrides);
+        std::mem::swap(&mut self.updated, &mut copy.updated);
+    }
+
+    /// sizes are measured per directory, the workshop item id says which copy it was
+    pub fn set_size(&mut self, workshop_item_id: &str, bytes: u64) {
+        if self.source.workshop_item_id() == workshop_item_id {
+            self.size = Some(bytes);
+        } else if let Some(copy) = self
+            .other_copies
+            .iter_mut()
+            .find(|e| e.source.workshop_item_id() == workshop_item_id)
+        {
+            copy.size = Some(bytes);
+        }
+    }
+
+    /// workshop tags followed by the user's own
+    pub fn all_tags(&self) -> impl Iterator<Item = &String> {
+        self.tags.iter().flatten().chain(self.user.tags.iter())
+    }
+
+    /// adds a user tag if the mod doesn't have it yet, returns if it was added
+    pub fn add_tag(&mut self, tag: &str) -> bool {
+        // searches are split on spaces, so a tag with one could never be found
+        let tag = tag
+            .split_whitespace()
+            .collect::<Vec<_>>()
+            .join("-")
+            .to_lowercase();
+        if tag.is_empty() || self.user.tags.contains(&tag) {
+            return false;
+        }
+        self.user.tags.push(tag);
+        true
+    }
+
+    /// the size in the largest unit it's at least 1 of, like `12.3 MB`
+    pub fn format_size(bytes: u64) -> String {
+        const UNITS: [&str; 4] = ["B", "KB", "MB", "GB"];
+        let mut size = bytes as f64;
+        let mut unit = 0;
+        while size >= 1024.0 && unit < UNITS.len() - 1 {
+            size /= 1024.0;
+            unit += 1;
+        }
+        if unit == 0 {
+            format!("{bytes} B")
+        } else {
+            format!("{size:.1} {}", UNITS[unit])
+        }
+    }
+
+    /// `last_run` is when the manager last started, `None` on the first run so nothing is marked
+    pub fn mark_updated(&mut self, last_run: Option<SystemTime>) {
+        self.updated = updated_since(&self.source, self.modified, last_run);
+        for copy in self.other_copies.iter_mut() {
+            copy.updated = updated_since(&copy.source, copy.modified, last_run);
+        }
+    }
+
+    /// sets `kind` from the user's override, or back to what was detected without one
+    /// saving only writes enabled for normal mods, so a mod overridden to something else is saved disabled
+    pub fn apply_kind_override(&mut self) {
+        let detected = self.overridden_from.take().unwrap_or(self.kind);
+        let enabled = matches!(self.kind, ModKind::Normal(NormalMod { enabled: true }));
+        self.kind = match self.user.kind {
+            None => detected,
+            Some(KindOverride::Normal) => ModKind::Normal(NormalMod { enabled }),
+            Some(KindOverride::Translation) => ModKind::Translation,
+            Some(KindOverride::Gamemode) => ModKind::Gamemode,
+        };
+        if self.user.kind.is_some() {
+            self.overridden_from = Some(detected);
+        }
+    }
+
+    /// the context menu for editing what the user has set on the mod, returns if anything changed
+    /// `new_tag` is the text box for adding a tag, owned by the caller so it lives between frames
+    pub fn render_user_menu(&mut self, ui: &mut egui::Ui, new_tag: &mut String) -> bool {
+        let mut changed = ui
+            .checkbox(&mut self.user.hidden, "Hidden")
+            .on_hover_text("Hide this mod from the list, it stays enabled or disabled as it is")
+            .changed();
+        let kind_changed = ui
+            .horizontal(|ui| {
+                ui.label("Kind");
+                let mut changed = ui
+                    .selectable_value(&mut self.user.kind, None, "Detected")
+                    .on_hover_text("Use what the mod says it is, clearing any override")
+                    .changed();
+                for kind in KindOverride::ALL {
+                    changed |= ui
+                        .selectable_value(&mut self.user.kind, Some(kind), kind.label())
+                        .on_hover_text(format!(
+                            "Treat this as a {} mod",
+                            kind.label().to_lowercase()
+                        ))
+                        .changed();
+                }
+                changed
+            })
+            .inner;
+        if kind_changed {
+            self.apply_kind_override();
+            changed = true;
+        }
+        if !self.other_copies.is_empty() {
+            let picked = ui
+                .horizontal(|ui| {
+                    ui.label("Source");
+                    let _ = ui.selectable_label(true, self.source.kind().name());
+                    let mut picked = None;
+                    for (i, copy) in self.other_copies.iter().enumerate() {
+                        if ui
+                            .selectable_label(false, copy.source.kind().name())
+                            .on_hover_text("Load this copy instead, the game is told on save")
+                            .clicked()
+                        {
+                            picked = Some(i);
+                        }
+                    }
+                    picked
+                })
+                .inner;
+            if let Some(i) = picked {
+                self.use_copy(i);
+                changed = true;
+            }
+        }
+        ui.label("Note");
+        changed |= ui
+            .add(egui::TextEdit::multiline(&mut self.user.note).desired_rows(2))
+            .changed();
+        ui.separator();
+        ui.label("Tags");
+        if let Some(tags) = &self.tags {
+            ui.weak(format!("Workshop: {}", tags.join(", ")));
+        }
+        let mut removed = None;
+        for (i, tag) in self.user.tags.iter().enumerate() {
+            ui.horizontal(|ui| {
+                if ui.small_button("✖").on_hover_text("Remove tag").clicked() {
+                    removed = Some(i);
+                }
+                ui.label(tag);
+            });
+        }
+        if let Some(i) = removed {
+            self.user.tags.remove(i);
+            changed = true;
+        }
+        ui.horizontal(|ui| {
+            let response = ui.text_edit_singleline(new_tag);
+            let submitted = response.lost_focus() && ui.input(|i| i.key_pressed(Key::Enter));
+            if ui.button("Add").clicked() || submitted {
+                changed |= self.add_tag(new_tag);
+                new_tag.clear();
+            }
+        });
+        changed
+    }
+
+    pub fn matches(&self, conditions: &[Condition], context: &SearchContext) -> bool {
         conditions
             .iter()
-            .map(|x| x.matches(&self))
+            .map(|x| x.matches(&self, context))
             .reduce(|a, b| a && b)
             .unwrap_or(true)
     }
 
-    // returns the rect of the text and it's hover text for dragging
-    pub fn render(&mut self, ui: &mut egui::Ui, errored: bool) -> ModRenderResponse {
+    /// returns the rect of the text for dragging, its hover is [`Mod::render_hover`]
+    /// `compact` leaves out the source and kind icons and packs the row tighter
+    /// `show_id` puts the id after the name, which is what the mod config uses
+    pub fn render(
+        &mut self,
+        ui: &mut egui::Ui,
+        errored: bool,
+        compact: bool,
+        show_id: bool,
+    ) -> ModRenderResponse {
+        let errored = errored || self.load_error.is_some();
         let full = ui.horizontal(|ui| {
-            ui.fixed_size_group(28.0 * SCALE, |ui| match &mut self.kind {
+            if compact {
+                ui.spacing_mut().item_spacing.x = 4.0;
+                ui.spacing_mut().interact_size.y = 0.0;
+            }
+            let enabled_width = if compact { 14.0 } else { 28.0 };
+            ui.fixed_size_group(enabled_width * SCALE, |ui| match &mut self.kind {
                 ModKind::Normal(normal_mod) => {
                     ui.scope(|ui| {
                         if errored {
                             ui.disable();
                         }
                         ui.checkbox(&mut normal_mod.enabled, "")
-                            .on_hover_text("Enabled");
+                            .on_hover_text("Enabled")
+                            .on_disabled_hover_text(match &self.load_error {
+                                Some(e) => format!("This mod failed to load:\n{e}"),
+                                None => "The mod list failed to load".to_owned(),
+                            });
                     });
                 }
                 _ => {}
             });
 
-            ui.fixed_size_group(20.0 * SCALE, |ui| match &self.source {
-                ModSource::Git(git_mod) => {
-                    let remote_url = git_mod.remote.clone();
-                    use egui::special_emojis::GIT;
-                    use egui::special_emojis::GITHUB;
-                    if let Some(url) = remote_url {
-                        ui.hyperlink_to(
-                            match git_mod.host {
-                                GitHost::Github => format!("{GITHUB}"),
-                                GitHost::Gitlab => format!("{GIT}"),
-                                GitHost::Other => format!("{GIT}"),
-                            },
-                            &url,
-                        )
-                        .on_hover_text(match &git_mod.host {
-                            GitHost::Github => format!("Github ({url})"),
-                            GitHost::Gitlab => format!("Gitlab ({url})"),
-                            GitHost::Other => format!("Unkown remote ({url})"),
-                        })
-                        .rect
-                        .width();
+            if !compact {
+                ui.fixed_size_group(20.0 * SCALE, |ui| {
+                    let source = self.source.kind();
+                    match (source.remote_url(), source.icon()) {
+                        (Some(url), icon) => {
+                            ui.hyperlink_to(icon.unwrap_or(format!("{LINK}")), url)
+                                .on_hover_text(source.hover_text());
+                        }
+                        (None, Some(icon)) => {
+                            ui.label(icon).on_hover_text(source.hover_text());
+                        }
+                        (None, None) => {}
                     }
-                }
-                ModSource::Steam(steam_mod) => {
-                    let steam_url = "https://steamcommunity.com/sharedfiles/filedetails/?id="
-                        .to_owned()
-                        + &steam_mod.workshop_id;
-                    ui.hyperlink_to(format!("{STEAM}"), &steam_url)
-                        .on_hover_text(format!("Steam ({steam_url})"))
-                        .rect
-                        .width();
-                }
-                _ => {}
-            });
+                });
 
-            ui.fixed_size_group(41.0 * SCALE, |ui| {
-                ui.horizontal(|ui| {
-                    ui.label(
-                        match &self.kind {
-                            ModKind::Normal(_) => NORMAL,
-                            ModKind::Translation => TRANSLATION,
-                            ModKind::Gamemode => GAMEMODE,
+                ui.fixed_size_group(41.0 * SCALE, |ui| {
+                    ui.horizontal(|ui| {
+                        ui.label(
+                            match &self.kind {
+                                ModKind::Normal(_) => NORMAL,
+                                ModKind::Translation => TRANSLATION,
+                                ModKind::Gamemode => GAMEMODE,
+                            }
+                            .to_string(),
+                        )
+                        .on_hover_text(match &self.kind {
+                            ModKind::Normal(_) => "Normal mod",
+                            ModKind::Translation => "Translation mod",
+                            ModKind::Gamemode => "Gamemode mod",
+                        });
+                        if self.unsafe_api {
+                            ui.label(RichText::new(format!("{UNSAFE}")).color(YELLOW))
+                                .on_hover_text("Unsafe mod");
                         }
-                        .to_string(),
-                    )
-                    .on_hover_text(match &self.kind {
-                        ModKind::Normal(_) => "Normal mod",
-                        ModKind::Translation => "Translation mod",
-                        ModKind::Gamemode => "Gamemode mod",
                     });
-                    if self.unsafe_api {
-                        ui.label(RichText::new(format!("{UNSAFE}")).color(YELLOW))
-                            .on_hover_text("Unsafe mod");
-                    }
                 });
-            });
+            }
 
-            let hover = "(".to_owned()
-                + &self.id
-                + if let ModSource::Steam(_) = &self.source {
-                    // hax to fix borrow stuff
-                    " - "
-                } else {
-                    ""
+            let mut name = RichText::new(self.display_name());
+            if self.user.hidden {
+                name = name.weak();
+            }
+            if self.load_error.is_some() {
+                name = name.color(ui.visuals().error_fg_color);
+            }
+            if compact {
+                name = name.small();
+            }
+            let rect = ui.label(name).rect;
+            if let Some(problem) = self.id_problem() {
+                ui.label(RichText::new(format!("{UNSAFE}")).color(ui.visuals().error_fg_color))
+                    .on_hover_text(problem);
+            }
+            if show_id {
+                let mut id = RichText::new(&self.id).monospace().weak();
+                if compact {
+                    id = id.small();
                 }
-                + if let ModSource::Steam(steam_mod) = &self.source {
-                    &steam_mod.workshop_id
-                } else {
-                    ""
-                }
-                + if &self.description != "" {
-                    ")\n\n"
-                } else {
-                    ")"
-                }
-                + &self.description;
-            let text_rect = ui.label(&self.name).rect;
-            (text_rect, hover)
+                ui.label(id);
+            }
+            if self.updated {
+                ui.label(RichText::new(format!("{UPDATED}")).color(YELLOW))
+                    .on_hover_text("Updated on the workshop since the manager last ran");
+            }
+            if !self.vanilla_overrides.is_empty() {
+                ui.label(RichText::new(format!("{OVERRIDES}")).color(YELLOW))
+                    .on_hover_text(format!(
+                        "Replaces vanilla files:\n{}",
+                        self.vanilla_overrides.join("\n")
+                    ));
+            }
+            rect
         });
         ModRenderResponse {
             full_rect: full.response.rect,
-            text_rect: full.inner.0,
-            text_hover: full.inner.1,
+            text_rect: full.inner,
+        }
+    }
+
+    /// shown when hovering the name, the ids get a line each with the description below
+    /// `compact` rows don't show the source and kind icons, so they are described here instead
+    pub fn render_hover(&self, ui: &mut egui::Ui, compact: bool) {
+        if let Some(e) = &self.load_error {
+            ui.colored_label(ui.visuals().error_fg_color, format!("Failed to load:\n{e}"));
+            ui.separator();
+        }
+        if compact {
+            let kind = match &self.kind {
+                ModKind::Normal(_) => "Normal mod",
+                ModKind::Translation => "Translation mod",
+                ModKind::Gamemode => "Gamemode mod",
+            };
+            let from = match self.source {
+                ModSource::Manual => "a manual install",
+                _ => self.source.kind().name(),
+            };
+            ui.label(format!("{kind} from {from}"));
+            if self.unsafe_api {
+                ui.colored_label(YELLOW, format!("{UNSAFE} Unsafe mod"));
+            }
+        }
+        if let Some(detected) = self.overridden_from {
+            let detected = match detected {
+                ModKind::Normal(_) => "normal",
+                ModKind::Translation => "translation",
+                ModKind::Gamemode => "gamemode",
+            };
+            ui.label(format!(
+                "Kind overridden, the mod says it is a {detected} mod"
+            ));
+        }
+        ui.label(format!("Mod id: {}", self.id));
+        if !self.other_copies.is_empty() {
+            let others = self
+                .other_copies
+                .iter()
+                .map(|e| e.source.kind().name())
+                .collect::<Vec<_>>()
+                .join(", ");
+            ui.colored_label(
+                YELLOW,
+                format!("Other installed copies: {others}, right click to pick which one is used"),
+            );
+        }
+        if let Some(author) = &self.author {
+            ui.label(format!("Author: {author}"));
+        }
+        if let ModSource::Steam(steam_mod) = &self.source {
+            ui.label(format!("Workshop id: {}", steam_mod.workshop_id));
+        }
+        ui.label(match self.size {
+            Some(bytes) => format!("Size: {}", Mod::format_size(bytes)),
+            None => "Size: measuring…".to_owned(),
+        });
+        if !self.user.note.is_empty() {
+            ui.separator();
+            ui.label(RichText::new(format!("Note: {}", self.user.note)).italics());
+        }
+        if !self.description.is_empty() {
+            ui.separator();
+            ui.label(&self.description);
+        }
+    }
+}
+
+#[cfg(test)]
+impl Mod {
+    /// a manually installed mod named after its id with nothing else set, tests change what they need
+    pub fn test(id: &str, kind: ModKind) -> Mod {
+        Mod {
+            source: ModSource::Manual,
+            kind,
+            name: id.to_owned(),
+            id: id.to_owned(),
+            description: "".to_owned(),
+            unsafe_api: false,
+            settings_fold_open: false,
+            tags: None,
+            user: Default::default(),
+            load_error: None,
+            size: None,
+            modified: None,
+            vanilla_overrides: Vec::new(),
+            updated: false,
+            author: None,
+            overridden_from: None,
+            other_copies: Vec::new(),
         }
     }
 }
@@ -1,42 +1,88 @@
+use std::cmp::Ordering;
+use std::collections::HashSet;
+use std::fmt::{self, Display};
+use std::time::{SystemTime, UNIX_EPOCH};
+
 use crate::r#mod::GitHost;
 use crate::r#mod::ModKind;
 use crate::r#mod::ModSource;
 use crate::Mod;
 
-#[derive(Copy, Clone, Debug)]
+#[derive(Copy, Clone, Debug, PartialEq)]
 enum ConditionalVariant {
+    Big,
     Enabled,
     Gamemode,
     Git,
     Github,
     Gitlab,
+    HasRemote,
+    HasSettings,
+    Hidden,
     Manual,
+    ModWorkshop,
+    NoRemote,
     Normal,
+    Noted,
+    Overrides,
+    Recent,
     Steam,
     Safe,
+    Small,
     Tagged,
     Translation,
+    Unsafe,
+    Updated,
+    Visible,
 }
 
-const CONDITIONS: [(&str, ConditionalVariant); 11] = [
+const CONDITIONS: [(&str, ConditionalVariant); 24] = [
+    ("big", ConditionalVariant::Big),
     ("enabled", ConditionalVariant::Enabled),
     ("gamemode", ConditionalVariant::Gamemode),
     ("git", ConditionalVariant::Git),
     ("github", ConditionalVariant::Github),
     ("gitlab", ConditionalVariant::Gitlab),
+    ("has-remote", ConditionalVariant::HasRemote),
+    ("has-settings", ConditionalVariant::HasSettings),
+    ("hidden", ConditionalVariant::Hidden),
     ("manual", ConditionalVariant::Manual),
+    ("modworkshop", ConditionalVariant::ModWorkshop),
+    ("no-remote", ConditionalVariant::NoRemote),
     ("normal", ConditionalVariant::Normal),
+    ("noted", ConditionalVariant::Noted),
+    ("overrides", ConditionalVariant::Overrides),
+    ("recent", ConditionalVariant::Recent),
     ("steam", ConditionalVariant::Steam),
     ("safe", ConditionalVariant::Safe),
+    ("small", ConditionalVariant::Small),
     ("tagged", ConditionalVariant::Tagged),
     ("translation", ConditionalVariant::Translation),
+    ("unsafe", ConditionalVariant::Unsafe),
+    ("updated", ConditionalVariant::Updated),
+    ("visible", ConditionalVariant::Visible),
 ];
 
+/// mods at least this big on disk match #big
+const BIG_MOD_BYTES: u64 = 50 * 1024 * 1024;
+/// mods smaller than this on disk match #small
+const SMALL_MOD_BYTES: u64 = 1024 * 1024;
+/// how many days back #recent looks unless the state file says otherwise
+pub const DEFAULT_RECENT_DAYS: u64 = 7;
+
 impl ConditionalVariant {
-    fn new(pat: &str) -> Option<ConditionalVariant> {
+    fn new(pat: &str) -> Result<ConditionalVariant, ParseError> {
+        if pat.is_empty() {
+            return Err(ParseError::MissingCondition);
+        }
+        if let Some(exact) = CONDITIONS.iter().find(|e| e.0 == pat) {
+            return Ok(exact.1);
+        }
         let matching: Vec<_> = CONDITIONS.iter().filter(|e| e.0.starts_with(pat)).collect();
         if matching.len() == 1 {
-            Some(matching[0].1)
+            Ok(matching[0].1)
+        } else if matching.is_empty() {
+            Err(ParseError::UnknownCondition(pat.to_owned()))
         } else {
             // git prefixes github and gitlab, so it isn't searchable normally
             let starters: Vec<_> = matching
@@ -48,15 +94,20 @@ impl ConditionalVariant {
                 })
                 .collect();
             if starters.len() == 1 {
-                Some(starters[0].1)
+                Ok(starters[0].1)
             } else {
-                None
+                Err(ParseError::Ambiguous(
+                    pat.to_owned(),
+                    matching.iter().map(|e| e.0).collect(),
+                ))
             }
         }
     }
 
-    fn matches(&self, nmod: &Mod) -> Option<bool> {
+    fn matches(&self, nmod: &Mod, context: &SearchContext) -> Option<bool> {
         match &self {
+            // sizes are measured after loading, until then these don't filter anything
+            ConditionalVariant::Big => nmod.size.map(|e| e >= BIG_MOD_BYTES),
             ConditionalVariant::Enabled => {
                 if let ModKind::Normal(normal_mod) = &nmod.kind {
                     Some(normal_mod.enabled)
@@ -65,7 +116,7 @@ impl ConditionalVariant {
                 }
             }
             ConditionalVariant::Gamemode => Some(matches!(nmod.kind, ModKind::Gamemode)),
-            ConditionalVariant::Git => Some(matches!(nmod.source, ModSource::Git(..))),
+            ConditionalVariant::Git => Some(matches!(nmod.source, ModSource::Git(_))),
             ConditionalVariant::Github => {
                 if let ModSource::Git(source) = &nmod.source {
                     Some(matches!(source.host, GitHost::Github))
@@ -80,16 +131,81 @@ impl ConditionalVariant {
                     Some(false)
                 }
             }
+            ConditionalVariant::HasRemote => Some(nmod.source.kind().remote_url().is_some()),
+            ConditionalVariant::HasSettings => {
+                Some(context.with_settings.is_some_and(|e| e.contains(&nmod.id)))
+            }
+            ConditionalVariant::Hidden => Some(nmod.user.hidden),
             ConditionalVariant::Manual => Some(matches!(nmod.source, ModSource::Manual)),
+            ConditionalVariant::ModWorkshop => {
+                Some(matches!(nmod.source, ModSource::ModWorkshop(_)))
+            }
+            ConditionalVariant::NoRemote => Some(nmod.source.kind().remote_url().is_none()),
             ConditionalVariant::Normal => Some(matches!(nmod.kind, ModKind::Normal(..))),
-            ConditionalVariant::Steam => Some(matches!(nmod.source, ModSource::Steam(..))),
+            ConditionalVariant::Noted => Some(!nmod.user.note.is_empty()),
+            ConditionalVariant::Overrides => Some(!nmod.vanilla_overrides.is_empty()),
+            ConditionalVariant::Recent => context
+                .recent_since
+                .map(|since| nmod.modified.is_some_and(|e| e >= since)),
+            ConditionalVariant::Steam => Some(matches!(nmod.source, ModSource::Steam(_))),
             ConditionalVariant::Safe => Some(!nmod.unsafe_api),
-            ConditionalVariant::Tagged => Some(nmod.tags.is_some()),
+            ConditionalVariant::Small => nmod.size.map(|e| e < SMALL_MOD_BYTES),
+            ConditionalVariant::Tagged => Some(nmod.all_tags().next().is_some()),
             ConditionalVariant::Translation => Some(matches!(nmod.kind, ModKind::Translation)),
+            ConditionalVariant::Unsafe => Some(nmod.unsafe_api),
+            ConditionalVariant::Updated => Some(nmod.updated),
+            ConditionalVariant::Visible => Some(!nmod.user.hidden),
         }
     }
 }
 
+/// Why a search term couldn't be used, shown next to the broken term
+#[derive(Clone, Debug, PartialEq)]
+pub enum ParseError {
+    /// a lone `#` or `#!`
+    MissingCondition,
+    UnknownCondition(String),
+    /// the prefix and every condition it could be
+    Ambiguous(String, Vec<&'static str>),
+    /// a lone `:` or `:!`
+    MissingTag,
+    /// `:#` followed by something that isn't a number
+    BadTagCount(String),
+    UnknownSort(String),
+}
+
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ParseError::MissingCondition => write!(f, "Nothing after the #, add a special term"),
+            ParseError::UnknownCondition(pat) => write!(f, "No special term starts with {pat}"),
+            ParseError::Ambiguous(pat, options) => {
+                write!(f, "{pat} could be any of {}", options.join(", "))
+            }
+            ParseError::MissingTag => write!(f, "Nothing after the :, add a tag"),
+            ParseError::BadTagCount(count) => {
+                write!(f, "{count} isn't a number of tags, use something like :#2")
+            }
+            ParseError::UnknownSort(key) => write!(
+                f,
+                "Can't sort by {key}, use one of {}",
+                SORT_KEYS.map(|e| e.0).join(", ")
+            ),
+        }
+    }
+}
+
+/// What searching needs to know beyond the mods themselves, built fresh by the caller
+#[derive(Clone, Debug, Default)]
+pub struct SearchContext<'a> {
+    /// hidden mods are only shown if this is set or the search asks about them
+    pub show_hidden: bool,
+    /// ids of mods with at least one value in the settings file, `None` is the same as none having any
+    pub with_settings: Option<&'a HashSet<String>>,
+    /// mods changed after this match #recent, `None` makes it not filter anything
+    pub recent_since: Option<SystemTime>,
+}
+
 #[derive(Copy, Clone, Debug)]
 struct MetaCondition {
     conditional: ConditionalVariant,
@@ -97,14 +213,14 @@ struct MetaCondition {
 }
 
 impl MetaCondition {
-    fn matches(&self, nmod: &Mod) -> bool {
+    fn matches(&self, nmod: &Mod, context: &SearchContext) -> bool {
         self.conditional
-            .matches(nmod)
+            .matches(nmod, context)
             .map(|x| x ^ self.inverted)
             .unwrap_or(true)
     }
 
-    fn new(src: &str) -> Option<MetaCondition> {
+    fn new(src: &str) -> Result<MetaCondition, ParseError> {
         let inverted = src.chars().nth(0) == Some('!');
         ConditionalVariant::new(&src[(inverted as usize)..]).map(|x| MetaCondition {
             conditional: x,
@@ -113,14 +229,22 @@ impl MetaCondition {
     }
 }
 
+#[derive(Clone, Debug)]
+enum TagMatch {
+    /// some tag starts with this
+    Prefix(String),
+    /// `:#n`, the mod has at least n tags
+    AtLeast(usize),
+}
+
 #[derive(Clone, Debug)]
 struct TagCondition {
     inverted: bool,
-    tag: String,
+    tag: TagMatch,
 }
 
 impl TagCondition {
-    fn new(src: &str) -> Option<TagCondition> {
+    fn new(src: &str) -> Result<TagCondition, ParseError> {
         let mut inverted = false;
         let mut src = src;
         if src.chars().nth(0) == Some('!') {
@@ -128,24 +252,28 @@ impl TagCondition {
             inverted = true;
         }
         if src == "" {
-            return None;
+            return Err(ParseError::MissingTag);
         }
-        Some(TagCondition {
-            inverted,
-            tag: src.to_owned(),
-        })
+        let tag = match src.strip_prefix('#') {
+            Some(count) => TagMatch::AtLeast(
+                count
+                    .parse()
+                    .map_err(|_| ParseError::BadTagCount(count.to_owned()))?,
+            ),
+            None => TagMatch::Prefix(src.to_owned()),
+        };
+        Ok(TagCondition { inverted, tag })
     }
 
+    /// untagged mods are treated as having no tags, so `:a :b` only finds mods with both tags
     fn matches(&self, nmod: &Mod) -> bool {
-        // TODO: maybe we should have aliases? quality of life can't be searched due to spaces, but quality works well enough
-        if let Some(tags) = &nmod.tags {
-            tags.iter()
-                .map(|e| e.starts_with(&self.tag))
-                .fold(false, |acc, e| acc || e)
-                ^ self.inverted
-        } else {
-            true // NOTE: we have the #tagged option to allow for customising this
-        }
+        // TODO: maybe we should have aliases? quality of life has to be quoted as :"quality of life" due to the spaces
+        let mut tags = nmod.all_tags();
+        let matched = match &self.tag {
+            TagMatch::Prefix(prefix) => tags.any(|e| e.starts_with(prefix)),
+            TagMatch::AtLeast(count) => tags.count() >= *count,
+        };
+        matched ^ self.inverted
     }
 }
 
@@ -162,34 +290,676 @@ pub struct Condition(ConditionEnum);
 impl Condition {
     pub fn special_terms() -> String {
         let s =
-            "Use :tag or :!tag to search mod tags\nSpecial terms (use with # or #!):\n".to_owned();
+            concat!(
+            "Use :tag or :!tag to search mod tags (workshop and your own), and :#2 for mods with at least 2 tags\n",
+            "Untagged mods count as having no tags, so they never match :tag but always match :!tag\n",
+            "Use sort:size to list the biggest mods first, or sort:modified for the most recently changed\n",
+            "sort:name lists mods from A to Z with Mod 2 before Mod 10, sort:name-plain puts Mod 10 first\n",
+            "#recent finds mods changed in the last 7 days, set recent_days in state.toml to change that\n",
+            "#overrides finds mods that replace core vanilla files, which often break other mods\n",
+            "#unsafe finds mods that ask for the unrestricted api, #safe the ones that don't\n",
+            "Put | between terms to find mods matching either side, like #steam | #git\n",
+            "Quote text with spaces, like \"noita together\" or :\"quality of life\", quoted text is never a special term\n",
+            "Special terms (use with # or #!):\n"
+        )
+        .to_owned();
         CONDITIONS.iter().fold(s, |acc, e| acc + "\n" + e.0)
     }
 
-    pub fn new(src: &str) -> Option<Condition> {
-        match src.chars().nth(0) {
-            Some(c) => {
-                if c == '#' {
-                    MetaCondition::new(&src[1..].to_lowercase())
-                        .map(|x| Condition(ConditionEnum::Meta(x)))
-                } else if c == ':' {
-                    TagCondition::new(&src[1..].to_lowercase())
-                        .map(|x| Condition(ConditionEnum::Tag(x)))
-                } else {
-                    Some(Condition(ConditionEnum::Literal(src.to_lowercase())))
-                }
-            }
-            None => None,
+    /// `src` is a single non empty term
+    pub fn new(src: &str) -> Result<Condition, ParseError> {
+        if let Some(meta) = src.strip_prefix('#') {
+            MetaCondition::new(&meta.to_lowercase()).map(|x| Condition(ConditionEnum::Meta(x)))
+        } else if let Some(tag) = src.strip_prefix(':') {
+            TagCondition::new(&tag.to_lowercase()).map(|x| Condition(ConditionEnum::Tag(x)))
+        } else {
+            Ok(Condition(ConditionEnum::Literal(src.to_lowercase())))
         }
     }
 
-    pub fn matches(&self, nmod: &Mod) -> bool {
+    /// whether this is #hidden or #visible, which means hidden mods should be searched
+    fn is_about_hidden(&self) -> bool {
+        matches!(
+            &self.0,
+            ConditionEnum::Meta(MetaCondition {
+                conditional: ConditionalVariant::Hidden | ConditionalVariant::Visible,
+                ..
+            })
+        )
+    }
+
+    pub fn matches(&self, nmod: &Mod, context: &SearchContext) -> bool {
         match &self.0 {
-            ConditionEnum::Meta(meta) => meta.matches(nmod),
+            ConditionEnum::Meta(meta) => meta.matches(nmod, context),
             ConditionEnum::Literal(s) => {
-                nmod.name.to_lowercase().contains(s) || nmod.id.to_lowercase().contains(s)
+                nmod.display_name().to_lowercase().contains(s) || nmod.id.to_lowercase().contains(s)
             }
             ConditionEnum::Tag(tag) => tag.matches(nmod),
         }
     }
+
+    /// how well a plain text term matches, higher is better: name prefix, then anywhere in the name, then the id
+    /// `None` if it doesn't match or isn't plain text
+    pub fn score(&self, nmod: &Mod) -> Option<u32> {
+        let ConditionEnum::Literal(s) = &self.0 else {
+            return None;
+        };
+        let name = nmod.display_name().to_lowercase();
+        if name.starts_with(s) {
+            Some(3)
+        } else if name.contains(s) {
+            Some(2)
+        } else if nmod.id.to_lowercase().contains(s) {
+            Some(1)
+        } else {
+            None
+        }
+    }
+}
+
+/// What a `sort:` term orders the results by, biggest or newest first and names from A to Z
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum SortKey {
+    Size,
+    Modified,
+    /// numbers in names are compared by value, so Mod 2 comes before Mod 10
+    Name,
+    /// character by character, Mod 10 comes before Mod 2
+    NamePlain,
+}
+
+const SORT_KEYS: [(&str, SortKey); 4] = [
+    ("size", SortKey::Size),
+    ("modified", SortKey::Modified),
+    ("name", SortKey::Name),
+    ("name-plain", SortKey::NamePlain),
+];
+
+impl SortKey {
+    fn new(src: &str) -> Option<SortKey> {
+        SORT_KEYS.iter().find(|e| e.0 == src).map(|e| e.1)
+    }
+
+    /// `Less` means `a` is shown first
+    pub fn compare(self, a: &Mod, b: &Mod) -> Ordering {
+        let modified = |nmod: &Mod| {
+            nmod.modified
+                .and_then(|e| e.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |e| e.as_secs())
+        };
+        match self {
+            SortKey::Size => b.size.unwrap_or(0).cmp(&a.size.unwrap_or(0)),
+            SortKey::Modified => modified(b).cmp(&modified(a)),
+            SortKey::Name => natural_cmp(a.display_name(), b.display_name()),
+            SortKey::NamePlain => a
+                .display_name()
+                .to_lowercase()
+                .cmp(&b.display_name().to_lowercase()),
+        }
+    }
+}
+
+/// case insensitive, with runs of digits compared as numbers
+fn natural_cmp(a: &str, b: &str) -> Ordering {
+    let mut a_chars = a.chars().flat_map(char::to_lowercase).peekable();
+    let mut b_chars = b.chars().flat_map(char::to_lowercase).peekable();
+    loop {
+        match (a_chars.peek(), b_chars.peek()) {
+            (None, None) => return a.cmp(b),
+            (None, Some(_)) => return Ordering::Less,
+            (Some(_), None) => return Ordering::Greater,
+            (Some(x), Some(y)) if x.is_ascii_digit() && y.is_ascii_digit() => {
+                let take_number = |chars: &mut std::iter::Peekable<_>| {
+                    let mut digits = String::new();
+                    while let Some(c) = chars.next_if(char::is_ascii_digit) {
+                        digits.push(c);
+                    }
+                    digits.trim_start_matches('0').to_owned()
+                };
+                let x = take_number(&mut a_chars);
+                let y = take_number(&mut b_chars);
+                // without leading zeros the longer number is bigger
+                let ordering = x.len().cmp(&y.len()).then_with(|| x.cmp(&y));
+                if ordering.is_ne() {
+                    return ordering;
+                }
+            }
+            (Some(x), Some(y)) => {
+                let ordering = x.cmp(y);
+                if ordering.is_ne() {
+                    return ordering;
+                }
+                a_chars.next();
+                b_chars.next();
+            }
+        }
+    }
+}
+
+/// A search term with its quotes taken out
+struct Term {
+    text: String,
+    /// started with a quote, so it's plain text even if it looks like a special term
+    quoted: bool,
+}
+
+/// splits on spaces and `|` outside of double quotes, a quote that is never closed runs to the end
+fn tokenize(src: &str) -> Vec<Vec<Term>> {
+    let mut groups = vec![Vec::new()];
+    let mut text = String::new();
+    let mut quoted = false;
+    let mut in_quotes = false;
+    for c in src.chars() {
+        match c {
+            '"' => {
+                quoted |= text.is_empty() && !in_quotes;
+                in_quotes = !in_quotes;
+            }
+            ' ' | '|' if !in_quotes => {
+                if !text.is_empty() {
+                    let text = std::mem::take(&mut text);
+                    groups.last_mut().unwrap().push(Term { text, quoted });
+                }
+                quoted = false;
+                if c == '|' {
+                    groups.push(Vec::new());
+                }
+            }
+            _ => text.push(c),
+        }
+    }
+    if !text.is_empty() {
+        groups.last_mut().unwrap().push(Term { text, quoted });
+    }
+    groups
+}
+
+/// A whole search string split into its conditions, kept around so it only needs parsing when the text changes
+#[derive(Clone, Debug, Default)]
+pub struct ParsedSearch {
+    source: String,
+    /// split by `|`, a mod matches if it matches every condition of any group
+    pub groups: Vec<Vec<Condition>>,
+    /// terms that couldn't be parsed and why
+    pub broken_terms: Vec<(String, ParseError)>,
+    /// hidden mods are searched if the search mentions them
+    mentions_hidden: bool,
+    /// from a `sort:` term, the last one wins
+    pub sort: Option<SortKey>,
+    /// the enabled only toggle, adds an `#enabled` that isn't part of the text
+    enabled_only: bool,
+}
+
+impl ParsedSearch {
+    pub fn new(src: &str) -> ParsedSearch {
+        let mut groups = Vec::new();
+        let mut broken_terms = Vec::new();
+        let mut sort = None;
+        for branch in tokenize(src) {
+            let mut conditions = Vec::new();
+            for Term { text: term, quoted } in branch {
+                if quoted {
+                    conditions.push(Condition(ConditionEnum::Literal(term.to_lowercase())));
+                    continue;
+                }
+                if let Some(key) = term.strip_prefix("sort:") {
+                    match SortKey::new(&key.to_lowercase()) {
+                        Some(key) => sort = Some(key),
+                        // still being typed
+                        None if key.is_empty() => {}
+                        None => broken_terms
+                            .push((term.clone(), ParseError::UnknownSort(key.to_owned()))),
+                    }
+                    continue;
+                }
+                match Condition::new(&term) {
+                    Ok(condition) => conditions.push(condition),
+                    // a bare # or : is most likely still being typed, so it is ignored rather than flashed as broken
+                    Err(ParseError::MissingCondition | ParseError::MissingTag) => {}
+                    Err(e) => broken_terms.push((term, e)),
+                }
+            }
+            // an empty side of a | is still being typed, rather than matching everything
+            if !conditions.is_empty() {
+                groups.push(conditions);
+            }
+        }
+        ParsedSearch {
+            source: src.to_owned(),
+            mentions_hidden: groups.iter().flatten().any(Condition::is_about_hidden),
+            groups,
+            broken_terms,
+            sort,
+            enabled_only: false,
+        }
+    }
+
+    /// only enabled mods match, on top of whatever the text asks for
+    pub fn with_enabled_only(mut self) -> ParsedSearch {
+        if !self.enabled_only {
+            let enabled = Condition(ConditionEnum::Meta(MetaCondition {
+                conditional: ConditionalVariant::Enabled,
+                inverted: false,
+            }));
+            if self.groups.is_empty() {
+                self.groups.push(Vec::new());
+            }
+            self.groups.iter_mut().for_each(|e| e.push(enabled.clone()));
+            self.enabled_only = true;
+        }
+        self
+    }
+
+    pub fn enabled_only(&self) -> bool {
+        self.enabled_only
+    }
+
+    /// hidden mods are left out unless [`SearchContext::show_hidden`] is set or the search asks about them
+    pub fn matches(&self, nmod: &Mod, context: &SearchContext) -> bool {
+        (context.show_hidden || self.mentions_hidden || !nmod.user.hidden)
+            && (self.groups.is_empty() || self.groups.iter().any(|e| nmod.matches(e, context)))
+    }
+
+    /// results are sorted by relevance only for a single plain text term, anything else keeps load order
+    pub fn is_ranked(&self) -> bool {
+        let [group] = self.groups.as_slice() else {
+            return false;
+        };
+        // the toggle's condition is always last and doesn't stop a text search being ranked
+        let typed = &group[..group.len() - usize::from(self.enabled_only)];
+        matches!(typed, [Condition(ConditionEnum::Literal(_))])
+    }
+
+    /// whether results are shown in some order other than load order, which means they can't be dragged
+    pub fn reorders(&self) -> bool {
+        self.sort.is_some() || self.is_ranked()
+    }
+
+    /// see [`Condition::score`], only meaningful when [`ParsedSearch::is_ranked`]
+    pub fn score(&self, nmod: &Mod) -> Option<u32> {
+        self.groups
+            .first()
+            .and_then(|e| e.first())
+            .and_then(|e| e.score(nmod))
+    }
+
+    /// the search string this was parsed from
+    pub fn source(&self) -> &str {
+        &self.source
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        natural_cmp, Condition, ConditionalVariant, ParseError, ParsedSearch, SearchContext,
+        SortKey,
+    };
+    use std::cmp::Ordering;
+    use std::time::{Duration, SystemTime};
+
+    use crate::r#mod::{
+        GitHost, GitMod, Mod, ModKind, ModSource, ModWorkshopMod, NormalMod, SteamMod, UNNAMED,
+    };
+
+    fn tagged(tags: Option<&[&str]>) -> Mod {
+        let mut nmod = Mod::test("test", ModKind::Normal(NormalMod { enabled: true }));
+        nmod.name = "Test".to_owned();
+        nmod.tags = tags.map(|tags| tags.iter().map(|e| e.to_string()).collect());
+        nmod
+    }
+
+    fn matches(search: &str, nmod: &Mod) -> bool {
+        Condition::new(search)
+            .expect("Search should parse")
+            .matches(nmod, &SearchContext::default())
+    }
+
+    #[test]
+    fn tags() {
+        let both = tagged(Some(&["content", "quality of life"]));
+        let untagged = tagged(None);
+        assert!(matches(":cont", &both) && matches(":qual", &both));
+        assert!(!matches(":cont", &untagged));
+        assert!(matches(":!cont", &untagged));
+        assert!(matches(":#2", &both));
+        assert!(!matches(":#3", &both));
+        assert!(matches(":!#1", &untagged));
+        assert_eq!(
+            Condition::new(":#many").unwrap_err(),
+            ParseError::BadTagCount("many".to_owned())
+        );
+
+        let mut manual = tagged(None);
+        manual.user.tags.push("mine".to_owned());
+        assert!(matches(":mine", &manual));
+        assert!(matches("#tagged", &manual));
+        assert!(!matches("#tagged", &untagged));
+
+        manual.user.note = "crashes with the other one".to_owned();
+        assert!(matches("#noted", &manual));
+        assert!(!matches("#noted", &untagged));
+    }
+
+    #[test]
+    fn unnamed() {
+        let mut unnamed = tagged(None);
+        unnamed.name = UNNAMED.to_owned();
+        assert_eq!(unnamed.display_name(), "test");
+        assert!(!matches(UNNAMED, &unnamed));
+        assert!(matches("test", &unnamed));
+    }
+
+    #[test]
+    fn relevance() {
+        let mut prefix = tagged(None);
+        prefix.name = "Spell Lab".to_owned();
+        let mut middle = tagged(None);
+        middle.name = "Better Spells".to_owned();
+        let mut id_only = tagged(None);
+        id_only.id = "spell_tweaks".to_owned();
+        let search = ParsedSearch::new("spell");
+        assert!(search.is_ranked());
+        assert!(search.score(&prefix) > search.score(&middle));
+        assert!(search.score(&middle) > search.score(&id_only));
+        assert!(search.score(&tagged(None)).is_none());
+        assert!(!ParsedSearch::new("spell #enabled").is_ranked());
+        assert!(!ParsedSearch::new("#enabled").is_ranked());
+    }
+
+    #[test]
+    fn size() {
+        let mut big = tagged(None);
+        big.size = Some(100 * 1024 * 1024);
+        let mut small = tagged(None);
+        small.size = Some(10);
+        assert!(matches("#big", &big) && !matches("#big", &small));
+        assert!(matches("#small", &small) && !matches("#small", &big));
+        // not measured yet
+        assert!(matches("#big", &tagged(None)));
+
+        let search = ParsedSearch::new("sort:size #enabled");
+        assert_eq!(search.sort, Some(SortKey::Size));
+        assert!(search.reorders());
+        assert_eq!(
+            ParsedSearch::new("sort:colour").broken_terms,
+            [(
+                "sort:colour".to_owned(),
+                ParseError::UnknownSort("colour".to_owned())
+            )]
+        );
+    }
+
+    #[test]
+    fn name_sorts() {
+        let mut names = [
+            "Mod 10",
+            "mod 2",
+            "Mod 1",
+            "Mod 02b",
+            "Mod",
+            "Spells 100",
+            "Spells 99",
+        ];
+        names.sort_by(|a, b| natural_cmp(a, b));
+        assert_eq!(
+            names,
+            [
+                "Mod",
+                "Mod 1",
+                "mod 2",
+                "Mod 02b",
+                "Mod 10",
+                "Spells 99",
+                "Spells 100"
+            ]
+        );
+
+        let named = |name: &str| {
+            let mut nmod = tagged(None);
+            nmod.name = name.to_owned();
+            nmod
+        };
+        let (two, ten) = (named("Mod 2"), named("Mod 10"));
+        let natural = ParsedSearch::new("sort:name")
+            .sort
+            .expect("Sort should parse");
+        assert_eq!(natural.compare(&two, &ten), Ordering::Less);
+        let plain = ParsedSearch::new("sort:name-plain")
+            .sort
+            .expect("Sort should parse");
+        assert_eq!(plain.compare(&two, &ten), Ordering::Greater);
+    }
+
+    #[test]
+    fn hidden() {
+        let mut hidden = tagged(None);
+        hidden.user.hidden = true;
+        let hiding = SearchContext::default();
+        let showing = SearchContext {
+            show_hidden: true,
+            ..Default::default()
+        };
+        assert!(!ParsedSearch::new("").matches(&hidden, &hiding));
+        assert!(ParsedSearch::new("").matches(&hidden, &showing));
+        assert!(ParsedSearch::new("#hidden").matches(&hidden, &hiding));
+        assert!(!ParsedSearch::new("#visible").matches(&hidden, &hiding));
+        assert!(ParsedSearch::new("#visible").matches(&tagged(None), &hiding));
+    }
+
+    #[test]
+    fn has_settings() {
+        let with_settings = ["test".to_owned()].into();
+        let context = SearchContext {
+            with_settings: Some(&with_settings),
+            ..Default::default()
+        };
+        let mut other = tagged(None);
+        other.id = "other".to_owned();
+        assert!(ParsedSearch::new("#has-settings").matches(&tagged(None), &context));
+        assert!(!ParsedSearch::new("#has-settings").matches(&other, &context));
+        assert!(ParsedSearch::new("#!has-settings").matches(&other, &context));
+    }
+
+    #[test]
+    fn parse_errors() {
+        let error = |src: &str| Condition::new(src).unwrap_err();
+        assert_eq!(error("#"), ParseError::MissingCondition);
+        assert_eq!(error("#!"), ParseError::MissingCondition);
+        assert_eq!(error(":!"), ParseError::MissingTag);
+        assert_eq!(
+            error("#colour"),
+            ParseError::UnknownCondition("colour".to_owned())
+        );
+        assert_eq!(
+            error("#s"),
+            ParseError::Ambiguous("s".to_owned(), vec!["steam", "safe", "small"])
+        );
+        // git is a prefix of github and gitlab, so it wins rather than being ambiguous
+        assert!(Condition::new("#gi").is_ok());
+    }
+
+    #[test]
+    fn terms_in_progress() {
+        let search = ParsedSearch::new("  # #! : :! sort:  ");
+        assert!(search.broken_terms.is_empty());
+        assert!(search.groups.is_empty());
+        assert!(search.sort.is_none());
+        assert_eq!(ParsedSearch::new(" #colour ").broken_terms.len(), 1);
+    }
+
+    const BRANCHES: [&str; 8] = [
+        "#enabled",
+        "#!enabled",
+        "#steam",
+        "#manual",
+        ":a",
+        ":!a",
+        "test",
+        "other",
+    ];
+
+    #[quickcheck]
+    fn or_matches_any_branch(a: usize, b: usize, enabled: bool, tagged_a: bool) -> bool {
+        let context = SearchContext::default();
+        let mut nmod = tagged(tagged_a.then_some(&["a"][..]));
+        nmod.kind = ModKind::Normal(NormalMod { enabled });
+        let (a, b) = (BRANCHES[a % BRANCHES.len()], BRANCHES[b % BRANCHES.len()]);
+        let branch = |src: &str| ParsedSearch::new(src).matches(&nmod, &context);
+        ParsedSearch::new(&format!("{a} | {b}")).matches(&nmod, &context)
+            == (branch(a) || branch(b))
+    }
+
+    #[test]
+    fn or_groups() {
+        let context = SearchContext::default();
+        let nmod = tagged(Some(&["a"]));
+        // spaces bind tighter than |
+        assert!(ParsedSearch::new("#steam :b | test :a").matches(&nmod, &context));
+        assert!(!ParsedSearch::new("#steam :a | test :b").matches(&nmod, &context));
+        assert!(ParsedSearch::new("#steam|:a").matches(&nmod, &context));
+        // only the broken piece is reported, and an empty side is ignored
+        let search = ParsedSearch::new("#colour | :a | ");
+        assert_eq!(search.groups.len(), 1);
+        assert_eq!(search.broken_terms.len(), 1);
+        assert_eq!(search.broken_terms[0].0, "#colour");
+        assert!(!ParsedSearch::new("test | other").is_ranked());
+    }
+
+    #[test]
+    fn quoted_phrases() {
+        let context = SearchContext::default();
+        let mut nmod = tagged(Some(&["quality of life"]));
+        nmod.name = "Noita Together".to_owned();
+        assert!(ParsedSearch::new("\"noita together\"").matches(&nmod, &context));
+        assert!(!ParsedSearch::new("\"together noita\"").matches(&nmod, &context));
+        assert!(ParsedSearch::new(":\"quality of life\"").matches(&nmod, &context));
+        assert!(!ParsedSearch::new(":\"quality of death\"").matches(&nmod, &context));
+        // a quote that is never closed takes the rest of the search, | included
+        let search = ParsedSearch::new("#manual \"noita tog");
+        assert!(search.matches(&nmod, &context));
+        assert_eq!(search.groups.len(), 1);
+        assert_eq!(ParsedSearch::new("\"a | b").groups.len(), 1);
+        // quoted text is always plain text
+        let search = ParsedSearch::new("\"#colour\"");
+        assert!(search.broken_terms.is_empty());
+        assert!(search.is_ranked());
+    }
+
+    #[test]
+    fn enabled_only() {
+        let context = SearchContext::default();
+        let enabled = tagged(None);
+        let mut disabled = tagged(None);
+        disabled.kind = ModKind::Normal(NormalMod { enabled: false });
+        let search = ParsedSearch::new("test").with_enabled_only();
+        assert!(search.matches(&enabled, &context));
+        assert!(!search.matches(&disabled, &context));
+        assert!(search.is_ranked());
+        assert!(ParsedSearch::new("test").matches(&disabled, &context));
+    }
+
+    #[test]
+    fn source_keywords() {
+        let sources = [
+            ModSource::Git(GitMod {
+                remote: None,
+                host: GitHost::Other,
+            }),
+            ModSource::Steam(SteamMod {
+                workshop_id: "1".to_owned(),
+            }),
+            ModSource::ModWorkshop(ModWorkshopMod {
+                link: "https://modworkshop.net/mod/1".to_owned(),
+            }),
+            ModSource::Manual,
+        ];
+        // the keyword shown for each source has to find mods from exactly that source
+        for (i, source) in sources.iter().enumerate() {
+            let keyword = format!("#{}", source.kind().condition_keyword());
+            for (j, other) in sources.iter().enumerate() {
+                let mut nmod = tagged(None);
+                nmod.source = other.clone();
+                assert_eq!(matches(&keyword, &nmod), i == j, "{keyword}");
+            }
+        }
+    }
+
+    #[test]
+    fn unsafe_api() {
+        let context = SearchContext::default();
+        let mut nmod = tagged(None);
+        let unsafe_search = ParsedSearch::new("#unsafe");
+        let safe_search = ParsedSearch::new("#safe");
+        assert!(!unsafe_search.matches(&nmod, &context));
+        assert!(safe_search.matches(&nmod, &context));
+        nmod.unsafe_api = true;
+        assert!(unsafe_search.matches(&nmod, &context));
+        assert!(!safe_search.matches(&nmod, &context));
+        assert!(ParsedSearch::new("#!unsafe").matches(&tagged(None), &context));
+    }
+
+    #[test]
+    fn exact_conditions() {
+        assert_eq!(ConditionalVariant::new("git"), Ok(ConditionalVariant::Git));
+        assert_eq!(ConditionalVariant::new("gi"), Ok(ConditionalVariant::Git));
+        assert_eq!(
+            ConditionalVariant::new("gith"),
+            Ok(ConditionalVariant::Github)
+        );
+        assert_eq!(
+            ConditionalVariant::new("gitl"),
+            Ok(ConditionalVariant::Gitlab)
+        );
+        assert_eq!(
+            ConditionalVariant::new("un"),
+            Ok(ConditionalVariant::Unsafe)
+        );
+        // updated and unsafe share a first letter
+        assert!(matches!(
+            ConditionalVariant::new("u"),
+            Err(ParseError::Ambiguous(..))
+        ));
+    }
+
+    #[test]
+    fn recent() {
+        let now = SystemTime::now();
+        let mut fresh = tagged(None);
+        fresh.modified = Some(now);
+        let mut stale = tagged(None);
+        stale.modified = Some(now - Duration::from_secs(30 * 24 * 60 * 60));
+        let context = SearchContext {
+            recent_since: Some(now - Duration::from_secs(7 * 24 * 60 * 60)),
+            ..Default::default()
+        };
+        let recent = Condition::new("#recent").expect("Search should parse");
+        assert!(recent.matches(&fresh, &context));
+        assert!(!recent.matches(&stale, &context));
+        assert!(!recent.matches(&tagged(None), &context));
+
+        let sort = ParsedSearch::new("sort:modified")
+            .sort
+            .expect("Sort should parse");
+        assert_eq!(sort.compare(&fresh, &stale), Ordering::Less);
+    }
+
+    #[test]
+    fn updated() {
+        let now = SystemTime::now();
+        let mut steam = tagged(None);
+        steam.source = ModSource::Steam(SteamMod {
+            workshop_id: "1".to_owned(),
+        });
+        steam.modified = Some(now);
+        steam.mark_updated(Some(now - Duration::from_secs(60)));
+        assert!(matches("#updated", &steam));
+        steam.mark_updated(None);
+        assert!(!matches("#updated", &steam));
+
+        let mut manual = tagged(None);
+        manual.modified = Some(now);
+        manual.mark_updated(Some(now - Duration::from_secs(60)));
+        assert!(!matches("#updated", &manual));
+    }
 }
@@ -0,0 +1,154 @@
+use egui::special_emojis::{GIT, GITHUB};
+
+use super::{GitHost, GitMod, ModSource, ModWorkshopMod, SteamMod};
+use crate::icons::{FOLDER, MOD_WORKSHOP, STEAM};
+
+/// What the mod list needs to know about where a mod came from, so a new source only needs its own impl
+pub trait ModSourceKind {
+    /// shown in the source column, linked if there is a url, which gets a generic link icon if this is `None`
+    fn icon(&self) -> Option<String>;
+    /// hovering the icon, includes the url if there is one
+    fn hover_label(&self) -> String;
+    /// where the mod can be found online
+    fn remote_url(&self) -> Option<String>;
+    /// the special search term that finds mods from this source, only shown to the user
+    fn condition_keyword(&self) -> &'static str;
+    /// short capitalised label, like the source picker's buttons
+    fn name(&self) -> &'static str;
+
+    /// hovering the icon, with the search term for the other mods from here
+    fn hover_text(&self) -> String {
+        format!(
+            "{}\nSearch #{} to find every mod from here",
+            self.hover_label(),
+            self.condition_keyword()
+        )
+    }
+}
+
+impl ModSourceKind for GitMod {
+    fn icon(&self) -> Option<String> {
+        Some(match self.host {
+            GitHost::Github => format!("{GITHUB}"),
+            GitHost::Gitlab | GitHost::Other => format!("{GIT}"),
+        })
+    }
+
+    fn hover_label(&self) -> String {
+        let host = match self.host {
+            GitHost::Github => "Github",
+            GitHost::Gitlab => "Gitlab",
+            GitHost::Other => "Unkown remote",
+        };
+        match &self.remote {
+            Some(url) => format!("{host} ({url})"),
+            None => "Git without a remote".to_owned(),
+        }
+    }
+
+    fn remote_url(&self) -> Option<String> {
+        self.remote.clone()
+    }
+
+    fn condition_keyword(&self) -> &'static str {
+        "git"
+    }
+
+    fn name(&self) -> &'static str {
+        "Git"
+    }
+}
+
+impl ModSourceKind for SteamMod {
+    fn icon(&self) -> Option<String> {
+        Some(format!("{STEAM}"))
+    }
+
+    fn hover_label(&self) -> String {
+        format!("Steam ({})", self.url())
+    }
+
+    fn remote_url(&self) -> Option<String> {
+        Some(self.url())
+    }
+
+    fn condition_keyword(&self) -> &'static str {
+        "steam"
+    }
+
+    fn name(&self) -> &'static str {
+        "Steam"
+    }
+}
+
+impl SteamMod {
+    pub fn url(&self) -> String {
+        "https://steamcommunity.com/sharedfiles/filedetails/?id=".to_owned() + &self.workshop_id
+    }
+}
+
+impl ModSourceKind for ModWorkshopMod {
+    fn icon(&self) -> Option<String> {
+        Some(format!("{MOD_WORKSHOP}"))
+    }
+
+    fn hover_label(&self) -> String {
+        format!("ModWorkshop ({})", self.link)
+    }
+
+    fn remote_url(&self) -> Option<String> {
+        Some(self.link.clone())
+    }
+
+    fn condition_keyword(&self) -> &'static str {
+        "modworkshop"
+    }
+
+    fn name(&self) -> &'static str {
+        "ModWorkshop"
+    }
+}
+
+/// stands in for [`ModSource::Manual`], which has no data of its own
+pub struct ManualMod;
+
+impl ModSourceKind for ManualMod {
+    fn icon(&self) -> Option<String> {
+        Some(format!("{FOLDER}"))
+    }
+
+    fn hover_label(&self) -> String {
+        "Installed manually".to_owned()
+    }
+
+    fn remote_url(&self) -> Option<String> {
+        None
+    }
+
+    fn condition_keyword(&self) -> &'static str {
+        "manual"
+    }
+
+    fn name(&self) -> &'static str {
+        "Manual"
+    }
+}
+
+impl ModSource {
+    /// what the mod config stores for this source, `0` for anything that isn't from the workshop
+    pub fn workshop_item_id(&self) -> &str {
+        match self {
+            ModSource::Steam(steam_mod) => &steam_mod.workshop_id,
+            _ => "0",
+        }
+    }
+
+    pub fn kind(&self) -> &dyn ModSourceKind {
+        match self {
+            ModSource::Git(git_mod) => git_mod,
+            ModSource::Steam(steam_mod) => steam_mod,
+            ModSource::ModWorkshop(workshop_mod) => workshop_mod,
+            ModSource::Manual => &ManualMod,
+        }
+    }
+}